@@ -0,0 +1,40 @@
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use pmod::hash::{dl_hash_bytes, dl_hash_bytes_scalar, DLHash};
+
+/// Typical param table and resource names of 10 to 40 characters.
+const NAMES: &[&str] = &[
+    "NpcParam_0",
+    "EquipParamWeapon",
+    "ShopLineupParam_Recipe",
+    "msg/engus/item_dlc01.msgbnd",
+    "chr/c4100_div00.anibnd.dcx",
+    "map/m10_00_00_00/m10_00_00_00.msb.dcx",
+    "data0:/param/gameparam/gameparam.bnd.dcx",
+];
+
+fn strhash(c: &mut Criterion) {
+    let mut group = c.benchmark_group("strhash");
+
+    for name in NAMES {
+        group.throughput(Throughput::Bytes(name.len() as u64));
+
+        group.bench_with_input(BenchmarkId::new("scalar", name.len()), name, |b, name| {
+            b.iter(|| dl_hash_bytes_scalar(black_box(name).as_bytes()))
+        });
+
+        group.bench_with_input(BenchmarkId::new("unrolled", name.len()), name, |b, name| {
+            b.iter(|| dl_hash_bytes(black_box(name).as_bytes()))
+        });
+
+        group.bench_with_input(BenchmarkId::new("strhash", name.len()), name, |b, name| {
+            b.iter(|| black_box(name).strhash())
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, strhash);
+criterion_main!(benches);
@@ -10,9 +10,12 @@
 #endif
 
 #ifdef __cplusplus
+#include <cstddef>
 #include <cstdint>
 extern "C" {
 #else
+#include <stdbool.h>
+#include <stddef.h>
 #include <stdint.h>
 #include <wchar.h>
 #endif
@@ -60,6 +63,43 @@ PMOD_DLL void* pmod_replace_row(const char* table_name, int32_t id, void* data);
  */
 PMOD_DLL void* pmod_delete_row(const char* table_name, int32_t id);
 
+/**
+ * Get the number of rows that can be inserted in the table `table_name`
+ * without reallocating it.
+ * 
+ * `table_name` must not be null.
+ * 
+ * If the function fails it returns a negative value.
+ * 
+ */
+PMOD_DLL int32_t pmod_get_free_slot_count(const char* table_name);
+
+/**
+ * Get the id of the param row that `pmod_insert_row` would create next
+ * in the table `table_name`.
+ * 
+ * The table is never modified, so the function fails if it has no free rows.
+ * 
+ * `table_name` must not be null.
+ * 
+ * If the function fails it returns a negative value.
+ * 
+ */
+PMOD_DLL int32_t pmod_get_free_id(const char* table_name);
+
+/**
+ * Register the param file `file_data` of `file_size` bytes as a new param table
+ * named `table_name`.
+ * 
+ * The file must stay valid while the table is registered.
+ * 
+ * `table_name` and `file_data` must not be null.
+ * 
+ * If the function fails it returns `false`.
+ * 
+ */
+PMOD_DLL bool pmod_register_param_table(const char* table_name, void* file_data, size_t file_size);
+
 /**
  * Get a wide null terminated string from the message repository.
  * 
@@ -87,6 +127,9 @@ PMOD_DLL uint32_t pmod_insert_msg(uint32_t version, uint32_t category, wchar_t*
  * 
  * If the function fails it returns `NULL`.
  * 
+ * If the old string was allocated by pmod and is not used by another message,
+ * it is freed and `NULL` is returned.
+ * 
  */
 PMOD_DLL wchar_t* pmod_replace_msg(uint32_t version, uint32_t category, uint32_t id, wchar_t* data);
 
@@ -95,6 +138,9 @@ PMOD_DLL wchar_t* pmod_replace_msg(uint32_t version, uint32_t category, uint32_t
  * 
  * If the function fails it returns `NULL`.
  * 
+ * If the old string was allocated by pmod and is not used by another message,
+ * it is freed and `NULL` is returned.
+ * 
  */
 PMOD_DLL wchar_t* pmod_delete_msg(uint32_t version, uint32_t category, uint32_t id);
 
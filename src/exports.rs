@@ -67,13 +67,59 @@ pub unsafe extern "C" fn pmod_delete_row(
     ParamRepository::delete_row(table_name, id).ok()
 }
 
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pmod_get_free_slot_count(table_name: *const c_char) -> i32 {
+    if table_name.is_null() {
+        return -1;
+    }
+
+    let table_name = unsafe { CStr::from_ptr(table_name) };
+
+    ParamRepository::free_slot_count(table_name)
+        .ok()
+        .and_then(|count| i32::try_from(count).ok())
+        .unwrap_or(-1)
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pmod_get_free_id(table_name: *const c_char) -> i32 {
+    if table_name.is_null() {
+        return -1;
+    }
+
+    let table_name = unsafe { CStr::from_ptr(table_name) };
+
+    ParamRepository::peek_next_free_id(table_name).unwrap_or(-1)
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pmod_register_param_table(
+    table_name: *const c_char,
+    file_data: *mut u8,
+    file_size: usize,
+) -> bool {
+    let Some(file) = NonNull::new(file_data) else {
+        return false;
+    };
+
+    if table_name.is_null() {
+        return false;
+    }
+
+    let Ok(table_name) = unsafe { CStr::from_ptr(table_name) }.to_str() else {
+        return false;
+    };
+
+    unsafe { ParamRepository::register_table(table_name, file.cast(), file_size).is_ok() }
+}
+
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn pmod_get_msg(
     version: u32,
     category: u32,
     id: u32,
 ) -> Option<NonNull<u16>> {
-    MsgRepository::get_msg(version, category, id)
+    MsgRepository::get_msg(version, category, id).ok()
 }
 
 #[unsafe(no_mangle)]
@@ -82,7 +128,7 @@ pub unsafe extern "C" fn pmod_insert_msg(
     category: u32,
     data: *mut u16,
 ) -> Option<NonZeroU32> {
-    MsgRepository::insert_msg(version, category, None, NonNull::new(data))
+    MsgRepository::insert_msg(version, category, None, NonNull::new(data)).ok()
 }
 
 #[unsafe(no_mangle)]
@@ -93,6 +139,8 @@ pub unsafe extern "C" fn pmod_replace_msg(
     data: *mut u16,
 ) -> Option<NonNull<u16>> {
     MsgRepository::replace_msg(version, category, id, NonNull::new(data))
+        .ok()
+        .flatten()
 }
 
 #[unsafe(no_mangle)]
@@ -101,5 +149,7 @@ pub unsafe extern "C" fn pmod_delete_msg(
     category: u32,
     id: u32,
 ) -> Option<NonNull<u16>> {
-    MsgRepository::replace_msg(version, category, id, None)
+    MsgRepository::delete_msg(version, category, id)
+        .ok()
+        .flatten()
 }
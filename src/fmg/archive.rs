@@ -0,0 +1,177 @@
+//! Single file archives holding the decoded messages of every category of a version.
+//!
+//! The binary format starts with the magic `PMSG`, followed by the little endian
+//! `u32` format version and the `u32` message version. Each category follows as its
+//! `u32` index and the `u32` byte length of its entries, which are stored as the `u32` id,
+//! the `u32` length in code units and the UTF-16 code units of each message.
+
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+use super::{file::FileHeader, journal::write_json_string, msg_units};
+
+const MAGIC: [u8; 4] = *b"PMSG";
+
+const FORMAT_VERSION: u32 = 1;
+
+/// The container format written by [`MsgRepository::export_version`].
+///
+/// [`MsgRepository::export_version`]: super::MsgRepository::export_version
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    /// Length-prefixed binary format that can be imported back.
+    Binary,
+
+    /// JSON object keyed by category index, holding objects keyed by message id.
+    Json,
+}
+
+pub(super) fn null_instance() -> io::Error {
+    io::Error::new(io::ErrorKind::NotFound, "message repository is null")
+}
+
+pub(super) fn write<'a, W, I>(
+    mut w: W,
+    format: ArchiveFormat,
+    version: u32,
+    categories: I,
+) -> io::Result<()>
+where
+    W: Write + Seek,
+    I: Iterator<Item = (u32, &'a FileHeader)>,
+{
+    match format {
+        ArchiveFormat::Binary => {
+            w.write_all(&MAGIC)?;
+            w.write_all(&FORMAT_VERSION.to_le_bytes())?;
+            w.write_all(&version.to_le_bytes())?;
+
+            for (category, file) in categories {
+                w.write_all(&category.to_le_bytes())?;
+
+                let len_pos = w.stream_position()?;
+                w.write_all(&0u32.to_le_bytes())?;
+
+                for (id, data) in file.all_msgs_sorted() {
+                    let Some(units) = (unsafe { msg_units(data) }) else {
+                        continue;
+                    };
+
+                    w.write_all(&id.to_le_bytes())?;
+                    w.write_all(&(units.len() as u32).to_le_bytes())?;
+
+                    for unit in units {
+                        w.write_all(&unit.to_le_bytes())?;
+                    }
+                }
+
+                let end_pos = w.stream_position()?;
+                let len = u32::try_from(end_pos - len_pos - 4)
+                    .map_err(|_| io::Error::other("category too large"))?;
+
+                w.seek(SeekFrom::Start(len_pos))?;
+                w.write_all(&len.to_le_bytes())?;
+                w.seek(SeekFrom::Start(end_pos))?;
+            }
+
+            Ok(())
+        }
+        ArchiveFormat::Json => {
+            write!(w, "{{\"version\": {version}, \"categories\": {{")?;
+
+            for (i, (category, file)) in categories.enumerate() {
+                if i != 0 {
+                    w.write_all(b",")?;
+                }
+
+                write!(w, "\n  \"{category}\": {{")?;
+
+                for (j, (id, data)) in file.all_msgs_sorted().into_iter().enumerate() {
+                    if j != 0 {
+                        w.write_all(b",")?;
+                    }
+
+                    let text = unsafe { msg_units(data) }.map(String::from_utf16_lossy);
+
+                    write!(w, "\n    \"{id}\": ")?;
+                    write_json_string(&mut w, text.as_deref())?;
+                }
+
+                w.write_all(b"\n  }")?;
+            }
+
+            w.write_all(b"\n}}\n")
+        }
+    }
+}
+
+/// Reads a binary archive, calling `f` with the index and messages of each category
+/// and returning the sum of the values it returned.
+pub(super) fn read<R, F>(mut r: R, mut f: F) -> io::Result<usize>
+where
+    R: Read,
+    F: FnMut(u32, Vec<(u32, Vec<u16>)>) -> io::Result<usize>,
+{
+    let mut magic = [0; 4];
+    r.read_exact(&mut magic)?;
+
+    if magic != MAGIC || read_u32(&mut r)? != FORMAT_VERSION {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "not a message archive",
+        ));
+    }
+
+    // The version the archive was exported from
+    read_u32(&mut r)?;
+
+    let mut total = 0;
+
+    loop {
+        let category = match read_u32(&mut r) {
+            Ok(category) => category,
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e),
+        };
+
+        let len = read_u32(&mut r)?;
+
+        let mut bytes = Vec::new();
+        r.by_ref().take(len as u64).read_to_end(&mut bytes)?;
+
+        if bytes.len() != len as usize {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        let mut bytes = bytes.as_slice();
+        let mut msgs = Vec::new();
+
+        while !bytes.is_empty() {
+            let id = read_u32(&mut bytes)?;
+            let units_len = read_u32(&mut bytes)? as usize;
+
+            let (units_bytes, rest) = bytes
+                .split_at_checked(units_len.saturating_mul(2))
+                .ok_or(io::ErrorKind::UnexpectedEof)?;
+
+            bytes = rest;
+
+            let units = units_bytes
+                .chunks_exact(2)
+                .map(|c| u16::from_le_bytes([c[0], c[1]]))
+                .collect();
+
+            msgs.push((id, units));
+        }
+
+        total += f(category, msgs)?;
+    }
+
+    Ok(total)
+}
+
+fn read_u32<R: Read>(r: &mut R) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    r.read_exact(&mut bytes)?;
+
+    Ok(u32::from_le_bytes(bytes))
+}
@@ -1,9 +1,11 @@
 use std::{
     alloc::{GlobalAlloc, Layout},
-    mem,
+    collections::{btree_map, BTreeMap},
+    error, fmt, mem,
     num::{NonZeroU32, NonZeroU64},
     ptr::{self, NonNull},
     slice,
+    sync::{Mutex, PoisonError},
 };
 
 use crate::stdalloc::DLStdAllocator;
@@ -25,6 +27,62 @@ pub struct FileHeader {
     _unk20: u64,
 }
 
+/// Message data outside of the file it belongs to and the number of messages
+/// referencing it, see [`register_external_msg`].
+static EXTERNAL_MSGS: Mutex<BTreeMap<usize, usize>> = Mutex::new(BTreeMap::new());
+
+/// Possible message file manipulation errors.
+///
+/// New variants may be added in minor releases, so matches must have a wildcard arm.
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The message id or index is not in any group of the file.
+    MsgNotFound,
+
+    /// The offset of the message is null.
+    NullOffset,
+
+    /// The offset of the message lies outside the file and the data
+    /// was not registered with [`register_external_msg`].
+    OffsetOutOfBounds,
+
+    /// The message count or the file size would exceed its maximum.
+    Overflow,
+
+    /// Could not allocate a new file.
+    AllocationFailed,
+
+    /// The message groups of the file can't be parsed.
+    Malformed,
+
+    /// The file has no empty message slot after the requested id.
+    NoEmptySlot,
+}
+
+/// Message file manipulation result.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Byte order of a message file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Endianness {
+    LittleEndian,
+    BigEndian,
+}
+
+/// Metadata of a group of messages with contiguous ids.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MsgGroupInfo {
+    /// The id of the first message of the group.
+    pub first_id: u32,
+
+    /// The id of the last message of the group, inclusive.
+    pub last_id: u32,
+
+    /// The index of the first message of the group.
+    pub offset: u32,
+}
+
 #[repr(C)]
 #[derive(Clone, Copy)]
 struct MsgGroup {
@@ -35,16 +93,52 @@ struct MsgGroup {
 }
 
 impl FileHeader {
-    pub fn msg_index_by_id(&self, id: u32) -> Option<u32> {
+    /// Returns the byte order the file is encoded in.
+    pub fn endianness(&self) -> Endianness {
+        if self.endianness != 0xFF {
+            Endianness::LittleEndian
+        } else {
+            Endianness::BigEndian
+        }
+    }
+
+    /// Returns the index of the message with the id `id`.
+    ///
+    /// # Errors:
+    /// - [`Error::MsgNotFound`] if no group contains `id`.
+    pub fn msg_index_by_id(&self, id: u32) -> Result<u32> {
+        let group = self.group_by_id(id).ok_or(Error::MsgNotFound)?;
+
+        Ok(id - group.first_id + group.offset)
+    }
+
+    /// Returns the metadata of the group containing the message with the id `id`.
+    ///
+    /// # Errors:
+    /// - [`Error::MsgNotFound`] if no group contains `id`.
+    pub fn group_info_by_id(&self, id: u32) -> Result<MsgGroupInfo> {
+        let group = self.group_by_id(id).ok_or(Error::MsgNotFound)?;
+
+        Ok(MsgGroupInfo {
+            first_id: group.first_id,
+            last_id: group.last_id,
+            offset: group.offset,
+        })
+    }
+
+    fn group_by_id(&self, id: u32) -> Option<&MsgGroup> {
         let groups = self.msg_groups();
 
-        let mut left = 0;
-        let mut right = self.group_count as usize - 1;
+        // Categories without any groups are valid, but empty
+        let (first, last) = groups.first().zip(groups.last())?;
 
-        if id < groups[left].first_id || id > groups[right].last_id {
+        if id < first.first_id || id > last.last_id {
             return None;
         }
 
+        let mut left = 0;
+        let mut right = groups.len() - 1;
+
         while left <= right {
             let mid = (left + right) / 2;
             let group = &groups[mid];
@@ -53,33 +147,129 @@ impl FileHeader {
                 left = mid + 1;
             } else {
                 if group.first_id <= id {
-                    return Some(id - group.first_id + group.offset);
+                    return Some(group);
                 }
 
-                right = mid - 1;
+                right = mid.checked_sub(1)?;
             }
         }
 
         None
     }
 
-    pub fn msg_data_by_index(&self, index: u32) -> Option<NonNull<u16>> {
+    /// Returns the number of message slots in the file.
+    pub fn msg_count(&self) -> u32 {
+        self.msg_count
+    }
+
+    /// Returns the number of message groups in the file.
+    pub fn group_count(&self) -> u32 {
+        self.group_count
+    }
+
+    /// Returns the first id of the first group and the last id of the last group,
+    /// or [`None`] if the file has no groups.
+    pub fn id_range(&self) -> Option<(u32, u32)> {
+        let groups = self.msg_groups();
+
+        Some((groups.first()?.first_id, groups.last()?.last_id))
+    }
+
+    /// Returns an iterator over the ids and indices of all messages in the file,
+    /// in the order of their groups.
+    pub fn msg_ids(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+        self.msg_groups().iter().flat_map(|g| {
+            let len = g
+                .last_id
+                .checked_sub(g.first_id)
+                .map_or(0, |d| d as u64 + 1);
+
+            (0..len).map(move |i| (g.first_id + i as u32, g.offset.wrapping_add(i as u32)))
+        })
+    }
+
+    /// Returns an iterator over the ids and data of all non-null messages in the file,
+    /// in the order of their groups.
+    pub fn all_msgs(&self) -> impl Iterator<Item = (u32, NonNull<u16>)> + '_ {
+        self.msg_ids()
+            .filter_map(|(id, index)| Some((id, self.msg_data_by_index(index).ok()?)))
+    }
+
+    /// Returns whether the file has reached [`MAX_MSG_COUNT`] and has no empty
+    /// message slots left, meaning no more messages can be inserted.
+    pub fn is_full(&self) -> bool {
+        self.msg_count >= MAX_MSG_COUNT
+            && self
+                .msg_ids()
+                .all(|(_, index)| self.msg_data_by_index(index).is_ok())
+    }
+
+    /// Returns the ids and data of all non-null messages in the file, sorted by id.
+    ///
+    /// The sort is stable, so messages with duplicate ids in malformed files
+    /// keep their relative order.
+    pub fn all_msgs_sorted(&self) -> Vec<(u32, NonNull<u16>)> {
+        let mut ids = self.msg_ids().collect::<Vec<_>>();
+        ids.sort_by_key(|&(id, _)| id);
+
+        ids.into_iter()
+            .filter_map(|(id, index)| Some((id, self.msg_data_by_index(index).ok()?)))
+            .collect()
+    }
+
+    /// Returns a pointer to the data of the message at `index`.
+    ///
+    /// # Errors:
+    /// - [`Error::MsgNotFound`] if `index` is out of bounds.
+    /// - [`Error::NullOffset`] if the message is null.
+    /// - [`Error::OffsetOutOfBounds`] if its offset lies outside the file
+    ///   and the data was not registered with [`register_external_msg`].
+    pub fn msg_data_by_index(&self, index: u32) -> Result<NonNull<u16>> {
         let offsets =
             unsafe { slice::from_raw_parts(self.msg_offsets.as_ptr(), self.msg_count as _) };
 
-        let offset = *offsets.get(index as usize)?;
+        let offset = offsets
+            .get(index as usize)
+            .ok_or(Error::MsgNotFound)?
+            .ok_or(Error::NullOffset)?
+            .get();
 
-        NonNull::new(self.file_base().wrapping_byte_add(offset?.get() as _) as _)
+        let data = NonNull::new(self.file_base().wrapping_byte_add(offset as _) as _)
+            .ok_or(Error::OffsetOutOfBounds)?;
+
+        if self.msg_ptr_in_bounds(data) || is_external_msg(data) {
+            Ok(data)
+        } else {
+            Err(Error::OffsetOutOfBounds)
+        }
     }
+
+    /// Returns whether `ptr` lies within the allocation of the file,
+    /// which spans `file_size` bytes from the file header.
+    ///
+    /// Message data registered with [`register_external_msg`] is usually out of bounds.
+    pub fn msg_ptr_in_bounds(&self, ptr: NonNull<u16>) -> bool {
+        let offset = usize::wrapping_sub(ptr.as_ptr() as _, self.file_base() as _);
+
+        offset < self.file_size as usize
+    }
+
+    /// Replaces the data of the message at `index`, returning the old data if it was not null.
+    ///
+    /// New data outside of the file is registered with [`register_external_msg`]
+    /// and old data outside of the file is released.
+    ///
+    /// # Errors:
+    /// - [`Error::MsgNotFound`] if `index` is out of bounds.
     pub fn replace_msg_by_index(
         &mut self,
         index: u32,
         data: Option<NonNull<u16>>,
-    ) -> Option<NonNull<u16>> {
+    ) -> Result<Option<NonNull<u16>>> {
         let offsets =
             unsafe { slice::from_raw_parts_mut(self.msg_offsets.as_ptr(), self.msg_count as _) };
 
-        let offset = offsets.get_mut(index as usize)?;
+        let offset = offsets.get_mut(index as usize).ok_or(Error::MsgNotFound)?;
 
         let old_data = offset
             .and_then(|o| NonNull::new(self.file_base().wrapping_byte_add(o.get() as _) as _));
@@ -88,7 +278,32 @@ impl FileHeader {
             NonZeroU64::new(usize::wrapping_sub(data.as_ptr() as _, self.file_base() as _) as u64)
         });
 
-        old_data
+        if let Some(data) = data.filter(|&data| !self.msg_ptr_in_bounds(data)) {
+            register_external_msg(data);
+        }
+
+        if let Some(old_data) = old_data.filter(|&data| !self.msg_ptr_in_bounds(data)) {
+            release_external_msg(old_data);
+        }
+
+        Ok(old_data)
+    }
+
+    /// Swaps the data of the messages at `index_a` and `index_b`.
+    ///
+    /// # Errors:
+    /// - [`Error::MsgNotFound`] if either index is out of bounds.
+    pub fn swap_msgs_by_index(&mut self, index_a: u32, index_b: u32) -> Result<()> {
+        let offsets =
+            unsafe { slice::from_raw_parts_mut(self.msg_offsets.as_ptr(), self.msg_count as _) };
+
+        if index_a >= self.msg_count || index_b >= self.msg_count {
+            return Err(Error::MsgNotFound);
+        }
+
+        offsets.swap(index_a as _, index_b as _);
+
+        Ok(())
     }
 
     fn file_base(&self) -> *mut u8 {
@@ -104,15 +319,31 @@ impl FileHeader {
         }
     }
 
+    fn msg_groups_mut(&mut self) -> &mut [MsgGroup] {
+        unsafe {
+            slice::from_raw_parts_mut(
+                self.file_base().byte_add(mem::size_of::<Self>()) as _,
+                self.group_count as _,
+            )
+        }
+    }
+
+    /// Stores `data` in the first empty message slot with an id greater than `after`,
+    /// returning the id of the slot.
+    ///
+    /// # Errors:
+    /// - [`Error::NoEmptySlot`] if there is no such slot, see [`FileHeader::grow_reallocate`].
     pub fn try_insert_new_after(
         &mut self,
         after: NonZeroU32,
         data: Option<NonNull<u16>>,
-    ) -> Option<NonZeroU32> {
+    ) -> Result<NonZeroU32> {
         let new_offset = data.and_then(|data| {
             NonZeroU64::new(usize::wrapping_sub(data.as_ptr() as _, self.file_base() as _) as u64)
         });
 
+        let external = data.filter(|&data| !self.msg_ptr_in_bounds(data));
+
         let groups = self.msg_groups();
 
         let index = match groups.binary_search_by_key(&after.get(), |g| g.first_id) {
@@ -133,23 +364,138 @@ impl FileHeader {
                 if offset.is_none() {
                     *offset = new_offset;
 
-                    return NonZeroU32::new(group.first_id + i as u32);
+                    if let Some(data) = external {
+                        register_external_msg(data);
+                    }
+
+                    return NonZeroU32::new(group.first_id + i as u32).ok_or(Error::Malformed);
                 }
             }
         }
 
-        None
+        Err(Error::NoEmptySlot)
     }
 
-    pub fn grow_reallocate(&self, after: NonZeroU32) -> Option<&'static mut Self> {
-        let old_msg_count = self.msg_count;
-        let new_msg_count = Ord::min(old_msg_count.saturating_mul(2), MAX_MSG_COUNT);
+    /// Merges adjacent groups with contiguous id ranges into single groups,
+    /// rewriting the offsets array so the messages of each group are stored contiguously.
+    ///
+    /// Returns the new number of groups.
+    ///
+    /// # Errors:
+    /// - [`Error::Malformed`] if the groups could not be parsed,
+    ///   in which case the file is left untouched.
+    pub fn normalize_groups(&mut self) -> Result<u32> {
+        let offsets =
+            unsafe { slice::from_raw_parts_mut(self.msg_offsets.as_ptr(), self.msg_count as _) };
 
-        if new_msg_count == old_msg_count {
-            return None;
+        let mut new_groups = Vec::<MsgGroup>::with_capacity(self.group_count as _);
+        let mut new_offsets = Vec::with_capacity(offsets.len());
+
+        for group in self.msg_groups() {
+            let len = group
+                .last_id
+                .checked_sub(group.first_id)
+                .and_then(|d| d.checked_add(1))
+                .ok_or(Error::Malformed)?;
+
+            let start = group.offset as usize;
+            let group_offsets = offsets
+                .get(start..start + len as usize)
+                .ok_or(Error::Malformed)?;
+
+            new_offsets.extend_from_slice(group_offsets);
+
+            match new_groups.last_mut() {
+                Some(last) if last.last_id.checked_add(1) == Some(group.first_id) => {
+                    last.last_id = group.last_id;
+                }
+                _ => new_groups.push(MsgGroup {
+                    offset: (new_offsets.len() - len as usize) as u32,
+                    ..*group
+                }),
+            }
         }
 
-        let max_group_size = self.max_group_size.max(1);
+        if new_offsets.len() > offsets.len() {
+            return Err(Error::Malformed);
+        }
+
+        let (used, unused) = offsets.split_at_mut(new_offsets.len());
+
+        used.copy_from_slice(&new_offsets);
+        unused.fill(None);
+
+        self.msg_groups_mut()[..new_groups.len()].copy_from_slice(&new_groups);
+        self.group_count = new_groups.len() as u32;
+
+        let largest_group = new_groups
+            .iter()
+            .map(|g| (g.last_id - g.first_id).saturating_add(1))
+            .max();
+        self.max_group_size = self.max_group_size.max(largest_group.unwrap_or(0));
+
+        Ok(self.group_count)
+    }
+
+    /// Allocates a new file without any messages with [`DLStdAllocator`].
+    ///
+    /// Messages can be added to it after [`FileHeader::grow_reallocate`].
+    ///
+    /// # Errors:
+    /// - [`Error::AllocationFailed`] if the file could not be allocated.
+    pub fn new_empty() -> Result<&'static mut Self> {
+        let file_size = mem::size_of::<Self>();
+
+        unsafe {
+            let file_base = DLStdAllocator::default()
+                .alloc(Layout::from_size_align_unchecked(file_size, 16))
+                as *mut Self;
+
+            if file_base.is_null() {
+                return Err(Error::AllocationFailed);
+            }
+
+            file_base.write(Self {
+                file_size: file_size as u32,
+                ..Default::default()
+            });
+
+            Ok(&mut *file_base)
+        }
+    }
+
+    /// Clones the file into a new allocation with room for twice as many messages,
+    /// adding new groups after the message with the id `after`.
+    ///
+    /// Empty files are given room for a single group of messages,
+    /// the first of which has the id `after + 1`.
+    ///
+    /// New groups hold at most `group_size` messages, or the file's maximum group size
+    /// if it is [`None`]. Larger groups make id lookups faster.
+    ///
+    /// # Errors:
+    /// - [`Error::Overflow`] if the file already holds [`MAX_MSG_COUNT`] messages,
+    ///   or the new file size does not fit in a `u32`.
+    /// - [`Error::AllocationFailed`] if the new file could not be allocated.
+    pub fn grow_reallocate(
+        &self,
+        after: NonZeroU32,
+        group_size: Option<NonZeroU32>,
+    ) -> Result<&'static mut Self> {
+        let max_group_size = group_size
+            .map_or(self.max_group_size, NonZeroU32::get)
+            .clamp(1, MAX_MSG_COUNT);
+
+        let old_msg_count = self.msg_count;
+        let new_msg_count = if old_msg_count != 0 {
+            Ord::min(old_msg_count.saturating_mul(2), MAX_MSG_COUNT)
+        } else {
+            max_group_size
+        };
+
+        if new_msg_count <= old_msg_count {
+            return Err(Error::Overflow);
+        }
 
         let old_group_count = self.group_count;
         let new_group_count =
@@ -163,13 +509,14 @@ impl FileHeader {
             let new_file_size = u32::checked_add(
                 mem::size_of::<Self>() as _,
                 new_group_count * mem::size_of::<MsgGroup>() as u32,
-            )?;
+            )
+            .ok_or(Error::Overflow)?;
 
             let new_file_layout = Layout::from_size_align_unchecked(new_file_size as _, 16);
             let new_file_base = alloc.alloc(new_file_layout) as *mut Self;
 
             if new_file_base.is_null() {
-                return None;
+                return Err(Error::AllocationFailed);
             }
 
             let new_offsets_size = new_msg_count as usize * mem::size_of::<usize>();
@@ -181,7 +528,7 @@ impl FileHeader {
             let Some(new_msg_offsets) = NonNull::new(new_msg_offsets) else {
                 alloc.dealloc(new_file_base as _, new_file_layout);
 
-                return None;
+                return Err(Error::AllocationFailed);
             };
 
             new_file_base.write(Self {
@@ -197,10 +544,18 @@ impl FileHeader {
 
             for (new, old) in new_offsets_to_init.iter_mut().zip(old_offsets) {
                 if let Some(old) = old {
-                    *new = NonZeroU64::new(usize::wrapping_sub(
-                        old_file_base.wrapping_byte_add(old.get() as _) as _,
-                        new_file_base as _,
-                    ) as u64)
+                    let data = old_file_base.wrapping_byte_add(old.get() as _);
+
+                    // Messages of the old file lie outside the new file,
+                    // external messages stay registered
+                    if let Some(data) = NonNull::new(data as *mut u16) {
+                        if self.msg_ptr_in_bounds(data) {
+                            register_external_msg(data);
+                        }
+                    }
+
+                    *new =
+                        NonZeroU64::new(usize::wrapping_sub(data as _, new_file_base as _) as u64)
                 }
             }
 
@@ -278,11 +633,82 @@ impl FileHeader {
             (*new_file_base).group_count = new_group_count - not_inserted_groups;
             (*new_file_base).msg_count = new_msg_count - not_inserted_msgs;
 
-            Some(&mut *new_file_base)
+            Ok(&mut *new_file_base)
+        }
+    }
+}
+
+/// Registers message data that lies outside of the file it is inserted into,
+/// so that [`FileHeader::msg_data_by_index`] does not reject it as a corrupt offset.
+///
+/// Data is registered once for every message referencing it and stays registered
+/// until every such message was replaced or [`unregister_external_msg`] is called.
+///
+/// Data passed to [`FileHeader::replace_msg_by_index`] and
+/// [`FileHeader::try_insert_new_after`], as well as messages moved out of a file
+/// by [`FileHeader::grow_reallocate`], is registered automatically.
+///
+/// The registry is local to this copy of pmod.
+pub fn register_external_msg(data: NonNull<u16>) {
+    let mut external = EXTERNAL_MSGS.lock().unwrap_or_else(PoisonError::into_inner);
+    *external.entry(data.as_ptr() as usize).or_default() += 1;
+}
+
+/// Removes message data from the registry, no matter how many messages reference it.
+///
+/// Must be called before freeing registered data, so that a corrupt offset
+/// pointing to a reused allocation is not accepted.
+pub fn unregister_external_msg(data: NonNull<u16>) {
+    let mut external = EXTERNAL_MSGS.lock().unwrap_or_else(PoisonError::into_inner);
+    external.remove(&(data.as_ptr() as usize));
+}
+
+/// Returns whether message data was registered with [`register_external_msg`].
+pub fn is_external_msg(data: NonNull<u16>) -> bool {
+    let external = EXTERNAL_MSGS.lock().unwrap_or_else(PoisonError::into_inner);
+    external.contains_key(&(data.as_ptr() as usize))
+}
+
+/// Releases one registration of message data that was replaced out of a message.
+fn release_external_msg(data: NonNull<u16>) {
+    let mut external = EXTERNAL_MSGS.lock().unwrap_or_else(PoisonError::into_inner);
+
+    if let btree_map::Entry::Occupied(mut entry) = external.entry(data.as_ptr() as usize) {
+        *entry.get_mut() -= 1;
+
+        if *entry.get() == 0 {
+            entry.remove();
         }
     }
 }
 
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self, f)
+    }
+}
+
+impl error::Error for Error {}
+
+impl fmt::Debug for FileHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let groups = self
+            .msg_groups()
+            .iter()
+            .map(|g| g.first_id..=g.last_id)
+            .collect::<Vec<_>>();
+
+        f.debug_struct("FileHeader")
+            .field("version", &self.version)
+            .field("file_size", &self.file_size)
+            .field("group_count", &self.group_count)
+            .field("msg_count", &self.msg_count)
+            .field("max_group_size", &self.max_group_size)
+            .field("groups", &groups)
+            .finish()
+    }
+}
+
 impl Default for FileHeader {
     fn default() -> Self {
         Self {
@@ -299,3 +725,217 @@ impl Default for FileHeader {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A message file with `G` groups, `M` message slots and room for message data
+    /// in a single allocation.
+    #[repr(C)]
+    struct TestFile<const G: usize, const M: usize> {
+        header: FileHeader,
+        groups: [MsgGroup; G],
+        offsets: [Option<NonZeroU64>; M],
+        text: [u16; 8],
+    }
+
+    fn test_file<const G: usize, const M: usize>(ranges: [(u32, u32); G]) -> Box<TestFile<G, M>> {
+        let mut offset = 0;
+
+        let groups = ranges.map(|(first_id, last_id)| {
+            let group = MsgGroup {
+                offset,
+                first_id,
+                last_id,
+                _unk0c: 0,
+            };
+
+            offset += last_id - first_id + 1;
+            group
+        });
+
+        assert_eq!(offset as usize, M, "groups must cover every message slot");
+
+        let mut file = Box::new(TestFile {
+            header: FileHeader {
+                file_size: mem::size_of::<TestFile<G, M>>() as u32,
+                group_count: G as u32,
+                msg_count: M as u32,
+                ..Default::default()
+            },
+            groups,
+            offsets: [None; M],
+            text: [0; 8],
+        });
+
+        file.header.msg_offsets = NonNull::from(&mut file.offsets).cast();
+        file
+    }
+
+    #[test]
+    fn empty_category_has_no_messages() {
+        let file = test_file::<0, 0>([]);
+
+        assert_eq!(file.header.msg_index_by_id(0), Err(Error::MsgNotFound));
+        assert_eq!(file.header.msg_index_by_id(1), Err(Error::MsgNotFound));
+        assert_eq!(file.header.group_info_by_id(1), Err(Error::MsgNotFound));
+        assert_eq!(file.header.id_range(), None);
+    }
+
+    #[test]
+    fn msg_index_by_id_searches_groups() {
+        let file = test_file::<3, 21>([(10, 19), (30, 39), (50, 50)]);
+        let header = &file.header;
+
+        assert_eq!(header.msg_index_by_id(10), Ok(0));
+        assert_eq!(header.msg_index_by_id(19), Ok(9));
+        assert_eq!(header.msg_index_by_id(30), Ok(10));
+        assert_eq!(header.msg_index_by_id(39), Ok(19));
+        assert_eq!(header.msg_index_by_id(50), Ok(20));
+
+        for id in [0, 9, 20, 29, 40, 49, 51, u32::MAX] {
+            assert_eq!(
+                header.msg_index_by_id(id),
+                Err(Error::MsgNotFound),
+                "id {id}"
+            );
+        }
+
+        let group = MsgGroupInfo {
+            first_id: 30,
+            last_id: 39,
+            offset: 10,
+        };
+
+        assert_eq!(header.group_info_by_id(35), Ok(group));
+    }
+
+    #[test]
+    fn msg_index_by_id_single_group() {
+        let file = test_file::<1, 1>([(1, 1)]);
+
+        assert_eq!(file.header.msg_index_by_id(1), Ok(0));
+        assert_eq!(file.header.msg_index_by_id(0), Err(Error::MsgNotFound));
+        assert_eq!(file.header.msg_index_by_id(2), Err(Error::MsgNotFound));
+    }
+
+    #[test]
+    fn msg_data_by_index_classifies_offsets() {
+        let mut file = test_file::<1, 4>([(1, 4)]);
+
+        let text = mem::offset_of!(TestFile<1, 4>, text) as u64;
+        let file_size = file.header.file_size as u64;
+
+        file.offsets = [
+            NonZeroU64::new(text),
+            None,
+            NonZeroU64::new(file_size),
+            NonZeroU64::new(u64::MAX),
+        ];
+
+        let header = &file.header;
+
+        assert_eq!(
+            header.msg_data_by_index(0),
+            Ok(NonNull::from(&file.text).cast())
+        );
+        assert_eq!(header.msg_data_by_index(1), Err(Error::NullOffset));
+        assert_eq!(header.msg_data_by_index(2), Err(Error::OffsetOutOfBounds));
+        assert_eq!(header.msg_data_by_index(3), Err(Error::OffsetOutOfBounds));
+        assert_eq!(header.msg_data_by_index(4), Err(Error::MsgNotFound));
+    }
+
+    #[test]
+    fn msg_data_by_index_accepts_external_msgs() {
+        let data = leaked_msg();
+
+        let mut file = test_file::<1, 1>([(1, 1)]);
+
+        let offset = usize::wrapping_sub(data.as_ptr() as _, file.header.file_base() as _);
+        file.offsets[0] = NonZeroU64::new(offset as u64);
+
+        assert!(!file.header.msg_ptr_in_bounds(data));
+        assert_eq!(
+            file.header.msg_data_by_index(0),
+            Err(Error::OffsetOutOfBounds)
+        );
+
+        register_external_msg(data);
+
+        assert!(is_external_msg(data));
+        assert_eq!(file.header.msg_data_by_index(0), Ok(data));
+    }
+
+    fn leaked_msg() -> NonNull<u16> {
+        // Leaked, so no other allocation can reuse the registered address
+        NonNull::from(Box::leak(Box::new([0u16; 4]))).cast()
+    }
+
+    #[test]
+    fn failed_insert_does_not_register() {
+        let data = leaked_msg();
+
+        let mut file = test_file::<1, 1>([(1, 1)]);
+        file.offsets[0] = NonZeroU64::new(mem::offset_of!(TestFile<1, 1>, text) as u64);
+
+        assert_eq!(
+            file.header
+                .try_insert_new_after(NonZeroU32::MIN, Some(data)),
+            Err(Error::NoEmptySlot)
+        );
+        assert!(!is_external_msg(data));
+    }
+
+    #[test]
+    fn replaced_external_msgs_are_released() {
+        let data = leaked_msg();
+
+        let mut file = test_file::<1, 2>([(1, 2)]);
+        let header = &mut file.header;
+
+        assert_eq!(
+            header.try_insert_new_after(NonZeroU32::MIN, Some(data)),
+            Ok(NonZeroU32::MIN)
+        );
+        assert_eq!(header.replace_msg_by_index(1, Some(data)), Ok(None));
+        assert!(is_external_msg(data));
+
+        assert_eq!(header.replace_msg_by_index(0, None), Ok(Some(data)));
+        assert!(
+            is_external_msg(data),
+            "still referenced by the second message"
+        );
+        assert_eq!(header.msg_data_by_index(1), Ok(data));
+
+        assert_eq!(header.replace_msg_by_index(1, None), Ok(Some(data)));
+        assert!(!is_external_msg(data));
+    }
+
+    #[test]
+    fn unregistered_msgs_are_rejected() {
+        let data = leaked_msg();
+
+        let mut file = test_file::<1, 1>([(1, 1)]);
+
+        assert_eq!(file.header.replace_msg_by_index(0, Some(data)), Ok(None));
+        assert_eq!(file.header.msg_data_by_index(0), Ok(data));
+
+        unregister_external_msg(data);
+
+        assert_eq!(
+            file.header.msg_data_by_index(0),
+            Err(Error::OffsetOutOfBounds)
+        );
+    }
+
+    #[test]
+    fn in_file_msgs_are_not_registered() {
+        let mut file = test_file::<1, 1>([(1, 1)]);
+        let text = NonNull::from(&file.text).cast::<u16>();
+
+        assert_eq!(file.header.replace_msg_by_index(0, Some(text)), Ok(None));
+        assert!(!is_external_msg(text));
+        assert_eq!(file.header.msg_data_by_index(0), Ok(text));
+    }
+}
@@ -0,0 +1,120 @@
+//! Opt-in journal of message edits made through [`MsgRepository`].
+//!
+//! [`MsgRepository`]: super::MsgRepository
+
+use std::{
+    io,
+    ptr::NonNull,
+    sync::{Mutex, PoisonError},
+};
+
+use super::msg_string;
+
+/// A single message edit recorded by the journal.
+///
+/// A missing `old_text` means the message was inserted or its slot was empty,
+/// a missing `new_text` means the message was deleted.
+#[derive(Clone, Debug)]
+pub struct JournalEntry {
+    pub version: u32,
+    pub category: u32,
+    pub id: u32,
+    pub old_text: Option<String>,
+    pub new_text: Option<String>,
+}
+
+static JOURNAL: Mutex<Option<Vec<JournalEntry>>> = Mutex::new(None);
+
+pub(super) fn enable() {
+    let mut journal = JOURNAL.lock().unwrap_or_else(PoisonError::into_inner);
+    journal.get_or_insert_with(Vec::new);
+}
+
+pub(super) fn disable() {
+    *JOURNAL.lock().unwrap_or_else(PoisonError::into_inner) = None;
+}
+
+pub(super) fn clear() {
+    let mut journal = JOURNAL.lock().unwrap_or_else(PoisonError::into_inner);
+
+    if let Some(journal) = journal.as_mut() {
+        journal.clear();
+    }
+}
+
+pub(super) fn entries() -> Vec<JournalEntry> {
+    let journal = JOURNAL.lock().unwrap_or_else(PoisonError::into_inner);
+    journal.clone().unwrap_or_default()
+}
+
+/// Records an edit if the journal is enabled.
+///
+/// Must be called while holding the repository lock, so that `old` and `new`
+/// are still valid and the decoded text reflects the state at the time of the edit.
+pub(super) fn record(
+    version: u32,
+    category: u32,
+    id: u32,
+    old: Option<NonNull<u16>>,
+    new: Option<NonNull<u16>>,
+) {
+    let mut journal = JOURNAL.lock().unwrap_or_else(PoisonError::into_inner);
+
+    if let Some(journal) = journal.as_mut() {
+        journal.push(JournalEntry {
+            version,
+            category,
+            id,
+            old_text: old.and_then(|old| unsafe { msg_string(old) }),
+            new_text: new.and_then(|new| unsafe { msg_string(new) }),
+        });
+    }
+}
+
+pub(super) fn write_json<W: io::Write>(mut w: W) -> io::Result<()> {
+    let entries = entries();
+
+    w.write_all(b"[")?;
+
+    for (i, entry) in entries.iter().enumerate() {
+        if i != 0 {
+            w.write_all(b",")?;
+        }
+
+        write!(
+            w,
+            "\n  {{\"version\": {}, \"category\": {}, \"id\": {}, \"old\": ",
+            entry.version, entry.category, entry.id
+        )?;
+        write_json_string(&mut w, entry.old_text.as_deref())?;
+
+        w.write_all(b", \"new\": ")?;
+        write_json_string(&mut w, entry.new_text.as_deref())?;
+
+        w.write_all(b"}")?;
+    }
+
+    w.write_all(b"\n]\n")
+}
+
+pub(super) fn write_json_string<W: io::Write>(w: &mut W, s: Option<&str>) -> io::Result<()> {
+    let Some(s) = s else {
+        return w.write_all(b"null");
+    };
+
+    w.write_all(b"\"")?;
+
+    for ch in s.chars() {
+        match ch {
+            '"' => w.write_all(b"\\\"")?,
+            '\\' => w.write_all(b"\\\\")?,
+            '\n' => w.write_all(b"\\n")?,
+            '\r' => w.write_all(b"\\r")?,
+            '\t' => w.write_all(b"\\t")?,
+            c if (c as u32) < 0x20 => write!(w, "\\u{:04x}", c as u32)?,
+            c => write!(w, "{c}")?,
+        }
+    }
+
+    w.write_all(b"\"")
+}
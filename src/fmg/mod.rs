@@ -1,12 +1,31 @@
 //! `fmg` format string manipulation utilities.
 //!
 //! - Retrieve with [`MsgRepository::get_msg`]
+//! - Retrieve with version fallback with [`MsgRepository::get_msg_with_fallback`]
 //! - Insert with [`MsgRepository::insert_msg`]
+//! - Create categories with [`MsgRepository::insert_category`]
 //! - Replace with [`MsgRepository::replace_msg`]
+//! - Replace in all versions with [`MsgRepository::set_msg_all_versions`]
+//! - Delete with [`MsgRepository::delete_msg`]
+//! - Overlay without modifying message files with [`MsgRepository::set_overlay`]
+//! - Record edits with [`MsgRepository::enable_journal`]
 
-use std::{fmt, num::NonZeroU32, ptr::NonNull, slice, sync::LazyLock};
+use std::{
+    alloc::{GlobalAlloc, Layout},
+    borrow::Cow,
+    collections::BTreeMap,
+    error, fmt, io,
+    num::NonZeroU32,
+    ops::Range,
+    ptr::{self, NonNull},
+    slice,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        LazyLock, Mutex, PoisonError,
+    },
+};
 
-use file::FileHeader;
+use file::{is_external_msg, unregister_external_msg, FileHeader, MsgGroupInfo, MAX_MSG_COUNT};
 use from_singleton::FromSingleton;
 use windows::core::w;
 
@@ -15,7 +34,15 @@ use crate::{
     stdalloc::DLStdAllocator,
 };
 
-mod file;
+mod archive;
+pub mod file;
+mod journal;
+mod overlay;
+mod reservation;
+
+pub use archive::ArchiveFormat;
+pub use journal::JournalEntry;
+pub use reservation::{ReservationHandle, ReservationOwner, MAX_OWNER_LEN, MAX_RESERVATIONS};
 
 #[repr(C)]
 pub struct MsgRepository {
@@ -38,43 +65,936 @@ struct FD4MessageManager {
     alloc: DLStdAllocator,
 }
 
+/// The maximum length of a message in UTF-16 code units, excluding the null terminator.
+pub const MAX_MSG_LEN: usize = u16::MAX as usize;
+
+/// Summary of a single message category, see [`MsgRepository::get_all_categories_detailed`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CategorySummary {
+    /// Index of the category.
+    pub index: u32,
+
+    /// Number of message slots in the category.
+    pub msg_count: u32,
+
+    /// Number of message groups in the category.
+    pub group_count: u32,
+
+    /// Number of non-null messages in the category, if they were counted.
+    pub non_empty: Option<u32>,
+}
+
+/// Outcome of [`MsgRepository::import_version`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ImportReport {
+    /// Number of overlaid messages.
+    pub overlaid: usize,
+
+    /// Category and id of every message that was skipped because it is not
+    /// well-formed UTF-16, see [`validate_utf16`], or contains a null character.
+    pub rejected: Vec<(u32, u32)>,
+}
+
+/// Possible message manipulation errors.
+#[derive(Clone, Copy, Debug)]
+pub enum Error {
+    /// Static [`MsgRepository`] instance is null.
+    NullInstance,
+
+    /// The version or the category does not exist.
+    CategoryNotFound,
+
+    /// The message with the specified id does not exist.
+    IdNotFound,
+
+    /// The message with the specified id is not null.
+    IdOccupied,
+
+    /// The text can't be stored as a null terminated message.
+    InvalidText,
+
+    /// The category has reached [`MAX_MSG_COUNT`] and has no empty slots left.
+    CapacityExceeded,
+
+    /// Could not reallocate the message file.
+    AllocationFailed,
+
+    /// The range of ids is empty or starts at 0.
+    InvalidRange,
+
+    /// The range of ids overlaps a range reserved by the contained owner.
+    RangeReserved(ReservationOwner),
+}
+
+/// Message manipulation result.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Message data allocated by pmod and its length in code units, see [`is_owned_msg`].
+static OWNED_MSGS: Mutex<BTreeMap<usize, usize>> = Mutex::new(BTreeMap::new());
+
+static GROWTH_GROUP_SIZE: AtomicU32 = AtomicU32::new(0);
+
 static MSG_REPOSITORY: LazyLock<StaticLock<MsgRepository>> = LazyLock::new(|| StaticLock::new());
 
 impl MsgRepository {
-    pub fn get_msg(version: u32, category: u32, id: u32) -> Option<NonNull<u16>> {
+    /// Returns whether the static [`MsgRepository`] instance has been created by the game,
+    /// without locking it.
+    ///
+    /// Message manipulation fails with [`Error::NullInstance`] until it has.
+    pub fn is_initialized() -> bool {
+        MSG_REPOSITORY.is_initialized()
+    }
+
+    /// Retrieves a pointer to the null terminated UTF-16 data of a message.
+    ///
+    /// Overlay entries set with [`MsgRepository::set_overlay`] take precedence over
+    /// the message files. A pointer to an overlay entry is valid until the entry is replaced,
+    /// removed or cleared. A pointer to data allocated by pmod is valid until the message
+    /// is replaced or deleted, see [`is_owned_msg`].
+    ///
+    /// # Errors:
+    /// - [`Error::NullInstance`] if static [`MsgRepository`] instance is null.
+    /// - [`Error::CategoryNotFound`] if the version or category does not exist.
+    /// - [`Error::IdNotFound`] if the message does not exist or is null.
+    pub fn get_msg(version: u32, category: u32, id: u32) -> Result<NonNull<u16>> {
+        if let Some(data) = overlay::get(version, category, id) {
+            return Ok(data);
+        }
+
+        let repo = MSG_REPOSITORY.read().ok_or(Error::NullInstance)?;
+
+        let file = repo
+            .file_by_category(version, category)
+            .ok_or(Error::CategoryNotFound)?;
+
+        let index = file.msg_index_by_id(id).map_err(|_| Error::IdNotFound)?;
+
+        file.msg_data_by_index(index).map_err(|_| Error::IdNotFound)
+    }
+
+    /// Like [`MsgRepository::get_msg`], but decodes the message,
+    /// replacing invalid UTF-16 with [`char::REPLACEMENT_CHARACTER`].
+    ///
+    /// # Errors:
+    /// - Any error returned by [`MsgRepository::get_msg`].
+    pub fn get_msg_string(version: u32, category: u32, id: u32) -> Result<String> {
+        if let Some(text) = overlay::get_string(version, category, id) {
+            return Ok(text);
+        }
+
+        let repo = MSG_REPOSITORY.read().ok_or(Error::NullInstance)?;
+
+        let file = repo
+            .file_by_category(version, category)
+            .ok_or(Error::CategoryNotFound)?;
+
+        let index = file.msg_index_by_id(id).map_err(|_| Error::IdNotFound)?;
+        let data = file.msg_data_by_index(index).map_err(|_| Error::IdNotFound)?;
+
+        unsafe { msg_string(data).ok_or(Error::IdNotFound) }
+    }
+
+    /// Tries to find a message in each of `versions` in order, returning the first
+    /// version that contains it together with a pointer to its data.
+    ///
+    /// Versions with a missing category or message are skipped, mirroring how the game
+    /// falls back to the text of version 0 when a localized version lacks an id.
+    pub fn get_msg_with_fallback(
+        versions: &[u32],
+        category: u32,
+        id: u32,
+    ) -> Option<(u32, NonNull<u16>)> {
+        let repo = MSG_REPOSITORY.read()?;
+
+        versions.iter().find_map(|&v| {
+            let data = overlay::get(v, category, id).or_else(|| repo.msg_by_id(v, category, id))?;
+
+            Some((v, data))
+        })
+    }
+
+    /// Like [`MsgRepository::get_msg_with_fallback`], but decodes the message,
+    /// replacing invalid UTF-16 with [`char::REPLACEMENT_CHARACTER`].
+    pub fn get_msg_string_with_fallback(
+        versions: &[u32],
+        category: u32,
+        id: u32,
+    ) -> Option<(u32, String)> {
+        let repo = MSG_REPOSITORY.read()?;
+
+        versions.iter().find_map(|&v| {
+            if let Some(text) = overlay::get_string(v, category, id) {
+                return Some((v, text));
+            }
+
+            let data = repo.msg_by_id(v, category, id)?;
+
+            Some((v, unsafe { msg_string(data)? }))
+        })
+    }
+
+    /// Overlays a message with a copy of `text` owned by pmod, without modifying
+    /// the message file.
+    ///
+    /// Overlay entries are returned by [`MsgRepository::get_msg`] and its variants
+    /// in place of the message file data, even if the message does not exist in the file.
+    /// They persist when the game reloads its message files.
+    ///
+    /// # Errors:
+    /// - [`Error::InvalidText`] if `text` contains a null character.
+    pub fn set_overlay(version: u32, category: u32, id: u32, text: &str) -> Result<()> {
+        let units = text.encode_utf16().collect::<Vec<_>>();
+        Self::set_overlay_utf16(version, category, id, &units)
+    }
+
+    /// Overlays a message with a copy of UTF-16 `units` like [`MsgRepository::set_overlay`],
+    /// for example from [`DLString::as_utf16`](crate::string::DLString::as_utf16).
+    ///
+    /// # Errors:
+    /// - [`Error::InvalidText`] if `units` contain a null character.
+    pub fn set_overlay_utf16(version: u32, category: u32, id: u32, units: &[u16]) -> Result<()> {
+        if units.contains(&0) {
+            return Err(Error::InvalidText);
+        }
+
+        overlay::set(version, category, id, units);
+
+        Ok(())
+    }
+
+    /// Removes an overlay entry, returning `true` if it existed.
+    ///
+    /// Pointers to the data of the entry are invalidated.
+    pub fn remove_overlay_entry(version: u32, category: u32, id: u32) -> bool {
+        overlay::remove(version, category, id)
+    }
+
+    /// Removes all overlay entries.
+    ///
+    /// Pointers to the data of the entries are invalidated.
+    pub fn clear_overlay() {
+        overlay::clear()
+    }
+
+    /// Returns the indices of all loaded versions.
+    pub fn get_all_versions() -> Option<Vec<u32>> {
+        let repo = MSG_REPOSITORY.read()?;
+
+        let versions =
+            (0..repo.inner.version_count).filter(|&v| repo.inner.by_version(v).is_some());
+
+        Some(versions.collect())
+    }
+
+    /// Returns the indices of all non-null categories of a version.
+    pub fn get_all_categories(version: u32) -> Option<Vec<u32>> {
+        let repo = MSG_REPOSITORY.read()?;
+        let holder = repo.inner.by_version(version)?;
+
+        Some(holder.categories().map(|(i, _)| i).collect())
+    }
+
+    /// Returns a lazy iterator over the indices of all non-null categories of a version.
+    ///
+    /// The categories are snapshotted as a bitmap under the read lock of the static
+    /// [`MsgRepository`] instance, which is released before returning. Categories
+    /// loaded or unloaded afterwards are not reflected by the iterator.
+    pub fn iter_categories(version: u32) -> Option<impl Iterator<Item = u32>> {
+        let repo = MSG_REPOSITORY.read()?;
+        let count = repo.inner.by_version(version)?.files().len() as u32;
+
+        let mut bitmap = vec![0u64; count.div_ceil(64) as usize];
+
+        for c in 0..count {
+            if repo.file_by_category(version, c).is_some() {
+                bitmap[c as usize / 64] |= 1 << (c % 64);
+            }
+        }
+
+        drop(repo);
+
+        Some((0..count).filter(move |&c| bitmap[c as usize / 64] & (1 << (c % 64)) != 0))
+    }
+
+    /// Returns a summary of all non-null categories of a version.
+    ///
+    /// Counting non-null messages requires walking every message of every category,
+    /// so it is only done if `count_non_empty` is `true`.
+    pub fn get_all_categories_detailed(
+        version: u32,
+        count_non_empty: bool,
+    ) -> Option<Vec<CategorySummary>> {
+        let repo = MSG_REPOSITORY.read()?;
+        let holder = repo.inner.by_version(version)?;
+
+        let summaries = holder.categories().map(|(index, file)| CategorySummary {
+            index,
+            msg_count: file.msg_count(),
+            group_count: file.group_count(),
+            non_empty: count_non_empty.then(|| file.all_msgs().count() as u32),
+        });
+
+        Some(summaries.collect())
+    }
+
+    /// Returns the metadata of the group containing a message.
+    pub fn group_for_id(version: u32, category: u32, id: u32) -> Option<MsgGroupInfo> {
         let repo = MSG_REPOSITORY.read()?;
         let file = repo.file_by_category(version, category)?;
 
-        let index = file.msg_index_by_id(id)?;
-        
-        file.msg_data_by_index(index)
+        file.group_info_by_id(id).ok()
     }
 
-    pub fn insert_msg(version: u32, category: u32, after: Option<NonZeroU32>, data: Option<NonNull<u16>>) -> Option<NonZeroU32> {
-        let mut repo = MSG_REPOSITORY.write()?;
+    /// Returns the inclusive range of message ids spanned by the groups of a category.
+    pub fn msg_id_range(version: u32, category: u32) -> Option<(u32, u32)> {
+        let repo = MSG_REPOSITORY.read()?;
+        let file = repo.file_by_category(version, category)?;
+
+        file.id_range()
+    }
+
+    /// Returns the ids and data of all non-null messages in a category,
+    /// in the order they are stored in the file.
+    pub fn get_all_msgs(version: u32, category: u32) -> Option<Vec<(u32, NonNull<u16>)>> {
+        let repo = MSG_REPOSITORY.read()?;
+        let file = repo.file_by_category(version, category)?;
+
+        Some(file.all_msgs().collect())
+    }
+
+    /// Returns the ids and data of all non-null messages in a category, sorted by id.
+    ///
+    /// The sort is stable, so messages with duplicate ids in malformed files
+    /// keep their relative order.
+    pub fn get_all_msgs_sorted(version: u32, category: u32) -> Option<Vec<(u32, NonNull<u16>)>> {
+        let repo = MSG_REPOSITORY.read()?;
+        let file = repo.file_by_category(version, category)?;
+
+        Some(file.all_msgs_sorted())
+    }
+
+    /// Finds the id of the first message in a category whose text is exactly `text`.
+    ///
+    /// If `ignore_case` is `true`, code units are compared after simple lowercase mapping.
+    pub fn find_id_by_text(
+        version: u32,
+        category: u32,
+        text: &str,
+        ignore_case: bool,
+    ) -> Option<u32> {
+        let repo = MSG_REPOSITORY.read()?;
+        let file = repo.file_by_category(version, category)?;
+
+        let query = encode_query(text, ignore_case);
+
+        let id = file
+            .all_msgs()
+            .find(|&(_, data)| unsafe { msg_eq(data, &query, ignore_case) })
+            .map(|(id, _)| id);
+
+        id
+    }
+
+    /// Finds the ids of all messages in a category whose text is exactly `text`,
+    /// in the order they are stored in the file.
+    ///
+    /// If `ignore_case` is `true`, code units are compared after simple lowercase mapping.
+    pub fn find_all_ids_by_text(
+        version: u32,
+        category: u32,
+        text: &str,
+        ignore_case: bool,
+    ) -> Option<Vec<u32>> {
+        let repo = MSG_REPOSITORY.read()?;
+        let file = repo.file_by_category(version, category)?;
 
-        let after = after.or_else(|| repo.new_after(category))?;
-        let file = repo.file_by_category_mut(version, category)?;
+        let query = encode_query(text, ignore_case);
+
+        Some(
+            file.all_msgs()
+                .filter(|&(_, data)| unsafe { msg_eq(data, &query, ignore_case) })
+                .map(|(id, _)| id)
+                .collect(),
+        )
+    }
+
+    /// Inserts a new message into the first empty slot after the id `after`,
+    /// returning its id.
+    ///
+    /// If `after` is [`None`], a category specific base id is used. The category is
+    /// reallocated if there are no empty slots left.
+    ///
+    /// If `after` is [`None`] and this module has reserved ids in the category
+    /// with [`MsgRepository::reserve_id_range`], the message is only inserted
+    /// into an empty slot with a reserved id.
+    ///
+    /// `data` must be valid for the lifetime of the message file.
+    ///
+    /// # Errors:
+    /// - [`Error::NullInstance`] if static [`MsgRepository`] instance is null.
+    /// - [`Error::CategoryNotFound`] if the version or category does not exist.
+    /// - [`Error::CapacityExceeded`] if the category or the reserved ids
+    ///   can't hold any more messages.
+    /// - [`Error::AllocationFailed`] if necessary reallocation failed.
+    pub fn insert_msg(
+        version: u32,
+        category: u32,
+        after: Option<NonZeroU32>,
+        data: Option<NonNull<u16>>,
+    ) -> Result<NonZeroU32> {
+        let mut repo = MSG_REPOSITORY.write().ok_or(Error::NullInstance)?;
+
+        if after.is_none() {
+            let reserved = reservation::local_ranges(category);
+
+            if !reserved.is_empty() {
+                return repo.insert_msg_reserved(version, category, &reserved, data);
+            }
+        }
+
+        let after = after
+            .or_else(|| repo.new_after(category))
+            .ok_or(Error::CategoryNotFound)?;
+
+        let file = repo
+            .file_by_category_mut(version, category)
+            .ok_or(Error::CategoryNotFound)?;
 
         let old_file = unsafe { file.as_mut() };
 
-        if let new_id @ Some(_) = old_file.try_insert_new_after(after, data) {
-            return new_id;
+        let new_id = match old_file.try_insert_new_after(after, data) {
+            Ok(new_id) => new_id,
+            Err(_) => {
+                if old_file.msg_count() >= MAX_MSG_COUNT {
+                    return Err(Error::CapacityExceeded);
+                }
+
+                let group_size = NonZeroU32::new(GROWTH_GROUP_SIZE.load(Ordering::Relaxed));
+
+                let new_file = old_file
+                    .grow_reallocate(after, group_size)
+                    .map_err(|_| Error::AllocationFailed)?;
+
+                *file = new_file.into();
+
+                new_file
+                    .try_insert_new_after(after, data)
+                    .map_err(|_| Error::CapacityExceeded)?
+            }
+        };
+
+        journal::record(version, category, new_id.get(), None, data);
+
+        Ok(new_id)
+    }
+
+    /// Creates a new empty category in a version, increasing the number of categories
+    /// of every version if `category` is out of bounds.
+    ///
+    /// Messages can be inserted into the category with [`MsgRepository::insert_msg`].
+    ///
+    /// Returns [`None`] if static [`MsgRepository`] instance is null, the version does not exist,
+    /// the category already exists or an allocation failed.
+    pub fn insert_category(version: u32, category: u32) -> Option<()> {
+        let mut repo = MSG_REPOSITORY.write()?;
+
+        repo.inner.by_version(version)?;
+
+        if category >= repo.inner.file_capacity {
+            repo.inner.grow_file_capacity(category.checked_add(1)?)?;
+        }
+
+        let mut holder = repo.inner.by_version(version)?;
+        let slot = holder.files_mut().get_mut(category as usize)?;
+
+        if slot.is_some() {
+            return None;
+        }
+
+        *slot = Some(NonNull::from(FileHeader::new_empty().ok()?));
+
+        Some(())
+    }
+
+    /// Reserves a range of message ids in a category for this module, for cooperation
+    /// with other modules in the process that insert messages.
+    ///
+    /// Reservations are not enforced, but they are respected by [`MsgRepository::insert_msg`]
+    /// when no id is specified. The range is released when the returned handle is dropped.
+    ///
+    /// `owner` is truncated to [`MAX_OWNER_LEN`] bytes.
+    ///
+    /// # Errors:
+    /// - [`Error::InvalidRange`] if `range` is empty or starts at 0.
+    /// - [`Error::RangeReserved`] if `range` overlaps an existing reservation.
+    /// - [`Error::CapacityExceeded`] if [`MAX_RESERVATIONS`] reservations are held.
+    pub fn reserve_id_range(
+        category: u32,
+        range: Range<u32>,
+        owner: &str,
+    ) -> Result<ReservationHandle> {
+        reservation::reserve(category, range, owner)
+    }
+
+    /// Inserts a new message into the empty slot with the id `id`.
+    ///
+    /// `data` must be valid for the lifetime of the message file.
+    ///
+    /// # Errors:
+    /// - [`Error::NullInstance`] if static [`MsgRepository`] instance is null.
+    /// - [`Error::CategoryNotFound`] if the version or category does not exist.
+    /// - [`Error::IdNotFound`] if no group of the category contains `id`.
+    /// - [`Error::IdOccupied`] if the message with the id `id` is not null.
+    pub fn insert_msg_at(
+        version: u32,
+        category: u32,
+        id: NonZeroU32,
+        data: Option<NonNull<u16>>,
+    ) -> Result<NonZeroU32> {
+        let mut repo = MSG_REPOSITORY.write().ok_or(Error::NullInstance)?;
+
+        let file = repo
+            .file_by_category_mut(version, category)
+            .ok_or(Error::CategoryNotFound)?;
+
+        let file = unsafe { file.as_mut() };
+
+        let index = file
+            .msg_index_by_id(id.get())
+            .ok()
+            .filter(|&i| i < file.msg_count())
+            .ok_or(Error::IdNotFound)?;
+
+        if file.msg_data_by_index(index).is_ok() {
+            return Err(Error::IdOccupied);
+        }
+
+        file.replace_msg_by_index(index, data).map_err(|_| Error::IdNotFound)?;
+
+        journal::record(version, category, id.get(), None, data);
+
+        Ok(id)
+    }
+
+    /// Inserts the data of the message with the id `src_id` into the empty slot
+    /// with the id `dst_id` in the same category, returning `dst_id`.
+    ///
+    /// Both messages share the same data. Overlay entries are not copied.
+    ///
+    /// Returns [`None`] if static [`MsgRepository`] instance is null, if the source message
+    /// does not exist or is null, or if it could not be inserted
+    /// with [`MsgRepository::insert_msg_at`], for example because `dst_id` is occupied.
+    pub fn copy_msg(version: u32, category: u32, src_id: u32, dst_id: u32) -> Option<NonZeroU32> {
+        let dst_id = NonZeroU32::new(dst_id)?;
+
+        let data = MSG_REPOSITORY
+            .read()?
+            .msg_by_id(version, category, src_id)?;
+
+        Self::insert_msg_at(version, category, dst_id, Some(data)).ok()
+    }
+
+    /// Inserts a copy of `text` allocated by pmod as a new message, returning its id.
+    ///
+    /// If `id` is [`Some`], the message is inserted with [`MsgRepository::insert_msg_at`],
+    /// otherwise with [`MsgRepository::insert_msg`].
+    ///
+    /// The copy is freed if the message could not be inserted.
+    ///
+    /// # Errors:
+    /// - [`Error::InvalidText`] if `text` contains a null character.
+    /// - [`Error::AllocationFailed`] if the copy could not be allocated.
+    /// - Any error returned by [`MsgRepository::insert_msg_at`]
+    ///   or [`MsgRepository::insert_msg`].
+    pub fn insert_msg_text(
+        version: u32,
+        category: u32,
+        id: Option<NonZeroU32>,
+        text: &str,
+    ) -> Result<NonZeroU32> {
+        let units = text.encode_utf16().collect::<Vec<_>>();
+        Self::insert_msg_utf16(version, category, id, &units)
+    }
+
+    /// Inserts a copy of UTF-16 `units` allocated by pmod as a new message like
+    /// [`MsgRepository::insert_msg_text`], for example from
+    /// [`DLString::as_utf16`](crate::string::DLString::as_utf16).
+    ///
+    /// # Errors:
+    /// - [`Error::InvalidText`] if `units` contain a null character.
+    /// - Same as [`MsgRepository::insert_msg_text`].
+    pub fn insert_msg_utf16(
+        version: u32,
+        category: u32,
+        id: Option<NonZeroU32>,
+        units: &[u16],
+    ) -> Result<NonZeroU32> {
+        if units.contains(&0) {
+            return Err(Error::InvalidText);
+        }
+
+        let data = alloc_msg(units).ok_or(Error::AllocationFailed)?;
+
+        let result = match id {
+            Some(id) => Self::insert_msg_at(version, category, id, Some(data)),
+            None => Self::insert_msg(version, category, None, Some(data)),
+        };
+
+        if result.is_err() {
+            unsafe { free_msg(data) };
+        }
+
+        result
+    }
+
+    /// Replaces the data of a message, returning a pointer to its old data
+    /// or [`None`] if it was null.
+    ///
+    /// `data` must be valid for the lifetime of the message file.
+    ///
+    /// Old data allocated by pmod that no other message references is freed
+    /// and [`None`] is returned in its place, see [`is_owned_msg`].
+    ///
+    /// # Errors:
+    /// - [`Error::NullInstance`] if static [`MsgRepository`] instance is null.
+    /// - [`Error::CategoryNotFound`] if the version or category does not exist.
+    /// - [`Error::IdNotFound`] if the message does not exist.
+    pub fn replace_msg(
+        version: u32,
+        category: u32,
+        id: u32,
+        data: Option<NonNull<u16>>,
+    ) -> Result<Option<NonNull<u16>>> {
+        let mut repo = MSG_REPOSITORY.write().ok_or(Error::NullInstance)?;
+        let old_data = repo.replace_msg_data(version, category, id, data)?;
+
+        Ok(unsafe { release_msg(old_data) })
+    }
+
+    /// Swaps the data of two messages of a category.
+    ///
+    /// Both messages are swapped under a single lock acquisition, so no intermediate state
+    /// is observable.
+    ///
+    /// # Errors:
+    /// - [`Error::NullInstance`] if static [`MsgRepository`] instance is null.
+    /// - [`Error::CategoryNotFound`] if the version or category does not exist.
+    /// - [`Error::IdNotFound`] if either message does not exist.
+    pub fn swap_msgs(version: u32, category: u32, id_a: u32, id_b: u32) -> Result<()> {
+        let mut repo = MSG_REPOSITORY.write().ok_or(Error::NullInstance)?;
+
+        let file = repo
+            .file_by_category_mut(version, category)
+            .ok_or(Error::CategoryNotFound)?;
+
+        let file = unsafe { file.as_mut() };
+
+        let index_a = file.msg_index_by_id(id_a).map_err(|_| Error::IdNotFound)?;
+        let index_b = file.msg_index_by_id(id_b).map_err(|_| Error::IdNotFound)?;
+
+        let data_a = file.msg_data_by_index(index_a).ok();
+        let data_b = file.msg_data_by_index(index_b).ok();
+
+        file.swap_msgs_by_index(index_a, index_b).map_err(|_| Error::IdNotFound)?;
+
+        journal::record(version, category, id_a, data_a, data_b);
+        journal::record(version, category, id_b, data_b, data_a);
+
+        Ok(())
+    }
+
+    /// Sets a message in every loaded version to a copy of `text` allocated by pmod,
+    /// returning the versions that were written to and whether writing succeeded.
+    ///
+    /// Versions where the category does not exist or `id` is not covered by its groups
+    /// are skipped. Versions where the message is null are skipped
+    /// unless `insert_missing` is `true`.
+    ///
+    /// Old data allocated by pmod that no other message references is freed.
+    ///
+    /// All versions are written to under a single lock acquisition.
+    /// Returns an empty [`Vec`] if `text` contains a null character
+    /// or the static [`MsgRepository`] instance is null.
+    pub fn set_msg_all_versions(
+        category: u32,
+        id: u32,
+        text: &str,
+        insert_missing: bool,
+    ) -> Vec<(u32, bool)> {
+        let mut results = Vec::new();
+
+        if text.contains('\0') {
+            return results;
         }
 
-        let new_file = old_file.grow_reallocate(after)?;
-        *file = new_file.into();
+        let Some(mut repo) = MSG_REPOSITORY.write() else {
+            return results;
+        };
+
+        let units = text.encode_utf16().collect::<Vec<_>>();
+
+        for version in 0..repo.inner.version_count {
+            let Some(file) = repo.file_by_category(version, category) else {
+                continue;
+            };
+
+            let index = file.msg_index_by_id(id).ok().filter(|&i| i < file.msg_count());
+
+            let Some(index) = index else {
+                continue;
+            };
+
+            if !insert_missing && file.msg_data_by_index(index).is_err() {
+                continue;
+            }
 
-        new_file.try_insert_new_after(after, data)
+            let Some(data) = alloc_msg(&units) else {
+                results.push((version, false));
+                continue;
+            };
+
+            let is_ok = match repo.replace_msg_data(version, category, id, Some(data)) {
+                Ok(old_data) => {
+                    unsafe { release_msg(old_data) };
+                    true
+                }
+                Err(_) => {
+                    unsafe { free_msg(data) };
+                    false
+                }
+            };
+
+            results.push((version, is_ok));
+        }
+
+        results
     }
 
-    pub fn replace_msg(version: u32, category: u32, id: u32, data: Option<NonNull<u16>>) -> Option<NonNull<u16>> {
+    /// Deletes a message, returning a pointer to its old data.
+    ///
+    /// Old data allocated by pmod that no other message references is freed
+    /// and [`None`] is returned in its place, see [`is_owned_msg`].
+    ///
+    /// # Errors:
+    /// - [`Error::NullInstance`] if static [`MsgRepository`] instance is null.
+    /// - [`Error::CategoryNotFound`] if the version or category does not exist.
+    /// - [`Error::IdNotFound`] if the message does not exist or is already null.
+    pub fn delete_msg(version: u32, category: u32, id: u32) -> Result<Option<NonNull<u16>>> {
+        let mut repo = MSG_REPOSITORY.write().ok_or(Error::NullInstance)?;
+
+        let old_data = repo
+            .replace_msg_data(version, category, id, None)?
+            .ok_or(Error::IdNotFound)?;
+
+        Ok(unsafe { release_msg(Some(old_data)) })
+    }
+
+    /// Returns whether a category has reached its maximum capacity,
+    /// in which case [`MsgRepository::insert_msg`] can't insert any more messages into it.
+    ///
+    /// Returns [`None`] if the category does not exist.
+    pub fn category_full(version: u32, category: u32) -> Option<bool> {
+        let repo = MSG_REPOSITORY.read()?;
+        let file = repo.file_by_category(version, category)?;
+
+        Some(file.is_full())
+    }
+
+    /// Sets the maximum number of messages in groups created when a category
+    /// has to be reallocated to fit new messages.
+    ///
+    /// If `group_size` is [`None`], the maximum group size stored in the file is used.
+    /// Larger groups make id lookups faster.
+    pub fn set_growth_group_size(group_size: Option<NonZeroU32>) {
+        GROWTH_GROUP_SIZE.store(group_size.map_or(0, NonZeroU32::get), Ordering::Relaxed);
+    }
+
+    /// Merges adjacent groups with contiguous id ranges in a category,
+    /// returning the new number of groups.
+    pub fn normalize_groups(version: u32, category: u32) -> Option<u32> {
         let mut repo = MSG_REPOSITORY.write()?;
         let file = unsafe { repo.file_by_category_mut(version, category)?.as_mut() };
 
-        let index = file.msg_index_by_id(id)?;
+        file.normalize_groups().ok()
+    }
+
+    /// Writes the decoded messages of every category of a version into a single archive,
+    /// one category at a time, with messages sorted by id.
+    ///
+    /// Archives in [`ArchiveFormat::Binary`] can be applied back with
+    /// [`MsgRepository::import_version`].
+    pub fn export_version<W: io::Write + io::Seek>(
+        version: u32,
+        writer: W,
+        format: ArchiveFormat,
+    ) -> io::Result<()> {
+        let repo = MSG_REPOSITORY.read().ok_or_else(archive::null_instance)?;
+
+        let holder = repo
+            .inner
+            .by_version(version)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "version not found"))?;
+
+        archive::write(writer, format, version, holder.categories())
+    }
+
+    /// Applies an archive written by [`MsgRepository::export_version`] in
+    /// [`ArchiveFormat::Binary`] to a version as overlay entries, one category at a time,
+    /// see [`MsgRepository::set_overlay`].
+    ///
+    /// The message files are not modified. Messages whose category or id does not exist
+    /// in the version are skipped.
+    ///
+    /// Messages with unpaired surrogates are skipped and reported, or sanitized with
+    /// [`sanitize_utf16_lossy`] if `lossy` is `true`.
+    pub fn import_version<R: io::Read>(
+        version: u32,
+        reader: R,
+        lossy: bool,
+    ) -> io::Result<ImportReport> {
+        let mut rejected = Vec::new();
+
+        let overlaid = archive::read(reader, |category, msgs| {
+            let repo = MSG_REPOSITORY.read().ok_or_else(archive::null_instance)?;
+
+            let Some(file) = repo.file_by_category(version, category) else {
+                return Ok(0);
+            };
+
+            let mut overlaid = 0;
+
+            for (id, units) in msgs {
+                if !file.msg_index_by_id(id).is_ok_and(|i| i < file.msg_count()) {
+                    continue;
+                }
+
+                let units = match validate_utf16(&units) {
+                    Ok(()) => Cow::Borrowed(units.as_slice()),
+                    Err(_) if lossy => Cow::Owned(sanitize_utf16_lossy(&units)),
+                    Err(_) => {
+                        rejected.push((category, id));
+                        continue;
+                    }
+                };
+
+                if Self::set_overlay_utf16(version, category, id, &units).is_err() {
+                    rejected.push((category, id));
+                    continue;
+                }
+
+                overlaid += 1;
+            }
+
+            Ok(overlaid)
+        })?;
+
+        Ok(ImportReport { overlaid, rejected })
+    }
+
+    /// Starts recording successful message edits in a process-local journal.
+    ///
+    /// The old and new text of each edit is decoded at the time of the edit,
+    /// so entries stay valid after further edits.
+    pub fn enable_journal() {
+        journal::enable();
+    }
+
+    /// Stops recording message edits and discards the journal.
+    pub fn disable_journal() {
+        journal::disable();
+    }
+
+    /// Discards all recorded journal entries, keeping the journal enabled.
+    pub fn clear_journal() {
+        journal::clear();
+    }
+
+    /// Returns a copy of all recorded journal entries, oldest first.
+    pub fn journal_entries() -> Vec<JournalEntry> {
+        journal::entries()
+    }
 
-        file.replace_msg_by_index(index, data)
+    /// Writes all recorded journal entries to `w` as a JSON array, oldest first.
+    pub fn export_journal<W: io::Write>(w: W) -> io::Result<()> {
+        journal::write_json(w)
+    }
+
+    fn msg_by_id(&self, version: u32, category: u32, id: u32) -> Option<NonNull<u16>> {
+        let file = self.file_by_category(version, category)?;
+
+        let index = file.msg_index_by_id(id).ok()?;
+
+        file.msg_data_by_index(index).ok()
+    }
+
+    fn insert_msg_reserved(
+        &mut self,
+        version: u32,
+        category: u32,
+        reserved: &[Range<u32>],
+        data: Option<NonNull<u16>>,
+    ) -> Result<NonZeroU32> {
+        let file = self
+            .file_by_category_mut(version, category)
+            .ok_or(Error::CategoryNotFound)?;
+
+        let old_file = unsafe { file.as_mut() };
+
+        let file = match empty_reserved_slot(old_file, reserved) {
+            Some(_) => old_file,
+            None => {
+                if old_file.msg_count() >= MAX_MSG_COUNT {
+                    return Err(Error::CapacityExceeded);
+                }
+
+                let after = NonZeroU32::new(reserved[0].start - 1).unwrap_or(NonZeroU32::MIN);
+                let group_size = NonZeroU32::new(GROWTH_GROUP_SIZE.load(Ordering::Relaxed));
+
+                let new_file = old_file
+                    .grow_reallocate(after, group_size)
+                    .map_err(|_| Error::AllocationFailed)?;
+
+                *file = new_file.into();
+
+                new_file
+            }
+        };
+
+        let (id, index) = empty_reserved_slot(file, reserved).ok_or(Error::CapacityExceeded)?;
+
+        file.replace_msg_by_index(index, data).map_err(|_| Error::CapacityExceeded)?;
+
+        journal::record(version, category, id.get(), None, data);
+
+        Ok(id)
+    }
+
+    fn replace_msg_data(
+        &mut self,
+        version: u32,
+        category: u32,
+        id: u32,
+        data: Option<NonNull<u16>>,
+    ) -> Result<Option<NonNull<u16>>> {
+        let file = self
+            .file_by_category_mut(version, category)
+            .ok_or(Error::CategoryNotFound)?;
+
+        let file = unsafe { file.as_mut() };
+
+        let index = file
+            .msg_index_by_id(id)
+            .ok()
+            .filter(|&i| i < file.msg_count())
+            .ok_or(Error::IdNotFound)?;
+
+        let old_data = file
+            .replace_msg_by_index(index, data)
+            .map_err(|_| Error::IdNotFound)?;
+
+        journal::record(version, category, id, old_data, data);
+
+        Ok(old_data)
     }
 
     fn file_by_category(&self, version: u32, category: u32) -> Option<&FileHeader> {
@@ -103,6 +1023,204 @@ impl MsgRepository {
     }
 }
 
+/// Finds the first empty message slot with an id in one of `reserved`,
+/// returning its id and index.
+fn empty_reserved_slot(file: &FileHeader, reserved: &[Range<u32>]) -> Option<(NonZeroU32, u32)> {
+    file.msg_ids()
+        .filter(|&(id, index)| {
+            index < file.msg_count()
+                && reserved.iter().any(|r| r.contains(&id))
+                && file.msg_data_by_index(index).is_err()
+        })
+        .find_map(|(id, index)| Some((NonZeroU32::new(id)?, index)))
+}
+
+/// Encodes `s` as a null terminated UTF-16 message.
+///
+/// Returns [`None`] if `s` contains a null character, which would terminate the message early.
+pub fn encode_utf16_checked(s: &str) -> Option<Vec<u16>> {
+    if s.contains('\0') {
+        return None;
+    }
+
+    Some(s.encode_utf16().chain([0]).collect())
+}
+
+/// Checks that `units` are well-formed UTF-16, without any unpaired surrogates,
+/// which the game's text renderer does not handle.
+///
+/// Returns the index of the first unpaired surrogate on failure.
+pub fn validate_utf16(units: &[u16]) -> std::result::Result<(), usize> {
+    let mut index = 0;
+
+    for ch in char::decode_utf16(units.iter().copied()) {
+        index += ch.map_err(|_| index)?.len_utf16();
+    }
+
+    Ok(())
+}
+
+/// Returns a copy of `units` with every unpaired surrogate
+/// replaced with [`char::REPLACEMENT_CHARACTER`].
+pub fn sanitize_utf16_lossy(units: &[u16]) -> Vec<u16> {
+    let mut result = Vec::with_capacity(units.len());
+    let mut buf = [0; 2];
+
+    for ch in char::decode_utf16(units.iter().copied()) {
+        let ch = ch.unwrap_or(char::REPLACEMENT_CHARACTER);
+        result.extend_from_slice(ch.encode_utf16(&mut buf));
+    }
+
+    result
+}
+
+/// Returns whether message data was allocated by pmod.
+pub fn is_owned_msg(data: NonNull<u16>) -> bool {
+    let owned = OWNED_MSGS.lock().unwrap_or_else(PoisonError::into_inner);
+    owned.contains_key(&(data.as_ptr() as usize))
+}
+
+/// Allocates a null terminated copy of `units` with [`DLStdAllocator`]
+/// and registers it as owned by pmod.
+fn alloc_msg(units: &[u16]) -> Option<NonNull<u16>> {
+    let layout = Layout::array::<u16>(units.len().checked_add(1)?).ok()?;
+
+    let data = unsafe {
+        let data = NonNull::new(DLStdAllocator::default().alloc(layout) as *mut u16)?;
+
+        ptr::copy_nonoverlapping(units.as_ptr(), data.as_ptr(), units.len());
+        data.add(units.len()).write(0);
+
+        data
+    };
+
+    let mut owned = OWNED_MSGS.lock().unwrap_or_else(PoisonError::into_inner);
+    owned.insert(data.as_ptr() as usize, units.len());
+
+    Some(data)
+}
+
+/// Frees message data allocated with [`alloc_msg`].
+///
+/// Does nothing if `data` is not owned by pmod.
+///
+/// # Safety
+/// `data` must not be referenced by any message file.
+unsafe fn free_msg(data: NonNull<u16>) {
+    let mut owned = OWNED_MSGS.lock().unwrap_or_else(PoisonError::into_inner);
+
+    let Some(len) = owned.remove(&(data.as_ptr() as usize)) else {
+        return;
+    };
+
+    unregister_external_msg(data);
+
+    unsafe {
+        let layout = Layout::array::<u16>(len + 1).unwrap_unchecked();
+        DLStdAllocator::default().dealloc(data.as_ptr() as _, layout);
+    }
+}
+
+/// Frees message data that was replaced out of a message if it was allocated by pmod
+/// and no other message references it, returning the data otherwise.
+///
+/// # Safety
+/// `data` must have been replaced out of a message file with
+/// [`FileHeader::replace_msg_by_index`], so that messages referencing it are registered.
+unsafe fn release_msg(data: Option<NonNull<u16>>) -> Option<NonNull<u16>> {
+    let data = data?;
+
+    if !is_owned_msg(data) || is_external_msg(data) {
+        return Some(data);
+    }
+
+    unsafe { free_msg(data) };
+
+    None
+}
+
+/// Returns the UTF-16 code units of the null terminated message at `data`,
+/// without the terminator.
+///
+/// Scans at most [`MAX_MSG_LEN`] code units and returns [`None`] if no terminator
+/// was found, so malformed entries can't cause reads past the end of the message.
+///
+/// # Safety
+/// `data` must be valid for reads up to its null terminator or [`MAX_MSG_LEN`] code units.
+unsafe fn msg_units<'a>(data: NonNull<u16>) -> Option<&'a [u16]> {
+    let mut len = 0;
+
+    while len < MAX_MSG_LEN {
+        if unsafe { *data.add(len).as_ptr() } == 0 {
+            return Some(unsafe { slice::from_raw_parts(data.as_ptr(), len) });
+        }
+
+        len += 1;
+    }
+
+    None
+}
+
+/// Compares the null terminated message at `data` with `query`,
+/// reading at most `query.len() + 1` code units.
+///
+/// # Safety
+/// `data` must be valid for reads up to its null terminator or `query.len() + 1` code units.
+unsafe fn msg_eq(data: NonNull<u16>, query: &[u16], ignore_case: bool) -> bool {
+    for (i, &expected) in query.iter().enumerate() {
+        let mut unit = unsafe { *data.add(i).as_ptr() };
+
+        if unit == 0 {
+            return false;
+        }
+
+        if ignore_case {
+            unit = fold_case(unit);
+        }
+
+        if unit != expected {
+            return false;
+        }
+    }
+
+    unsafe { *data.add(query.len()).as_ptr() == 0 }
+}
+
+fn encode_query(text: &str, ignore_case: bool) -> Vec<u16> {
+    let units = text.encode_utf16();
+
+    if ignore_case {
+        units.map(fold_case).collect()
+    } else {
+        units.collect()
+    }
+}
+
+/// Simple lowercase mapping of a UTF-16 code unit.
+///
+/// Units which are surrogates or don't map to a single BMP character are left untouched.
+fn fold_case(unit: u16) -> u16 {
+    let Some(ch) = char::from_u32(unit as u32) else {
+        return unit;
+    };
+
+    let mut lower = ch.to_lowercase();
+
+    match (lower.next(), lower.next()) {
+        (Some(l), None) => u16::try_from(l as u32).unwrap_or(unit),
+        _ => unit,
+    }
+}
+
+/// Decodes the null terminated message at `data`, replacing invalid UTF-16
+/// with [`char::REPLACEMENT_CHARACTER`].
+///
+/// # Safety
+/// See [`msg_units`].
+unsafe fn msg_string(data: NonNull<u16>) -> Option<String> {
+    unsafe { msg_units(data).map(String::from_utf16_lossy) }
+}
+
 impl FD4MessageManager {
     fn by_version(&self, v: u32) -> Option<FileHolder<'_>> {
         let versions =
@@ -115,6 +1233,58 @@ impl FD4MessageManager {
             file_capacity: self.file_capacity,
         })
     }
+
+    /// Reallocates the file arrays of every version to hold `file_capacity` files.
+    ///
+    /// The old arrays are leaked, since the game may still hold pointers to them.
+    fn grow_file_capacity(&mut self, file_capacity: u32) -> Option<()> {
+        let alloc = DLStdAllocator::default();
+        let layout = Layout::array::<Option<NonNull<FileHeader>>>(file_capacity as _).ok()?;
+
+        let versions =
+            unsafe { slice::from_raw_parts_mut(self.inner.as_ptr(), self.version_count as _) };
+
+        // Allocate every array first, so that a failure has no effect
+        let mut new_files = Vec::with_capacity(versions.len());
+
+        for version in versions.iter() {
+            if version.is_none() {
+                new_files.push(None);
+                continue;
+            }
+
+            let files = unsafe { alloc.alloc_zeroed(layout) } as *mut Option<NonNull<FileHeader>>;
+
+            match NonNull::new(files) {
+                Some(files) => new_files.push(Some(files)),
+                None => {
+                    for files in new_files.into_iter().flatten() {
+                        unsafe { alloc.dealloc(files.as_ptr() as _, layout) };
+                    }
+
+                    return None;
+                }
+            }
+        }
+
+        for (version, new_files) in versions.iter_mut().zip(new_files) {
+            if let (Some(old_files), Some(new_files)) = (version.as_mut(), new_files) {
+                unsafe {
+                    ptr::copy_nonoverlapping(
+                        old_files.as_ptr(),
+                        new_files.as_ptr(),
+                        self.file_capacity as _,
+                    );
+                }
+
+                *old_files = new_files;
+            }
+        }
+
+        self.file_capacity = file_capacity;
+
+        Some(())
+    }
 }
 
 impl<'a> FileHolder<'a> {
@@ -122,6 +1292,13 @@ impl<'a> FileHolder<'a> {
         unsafe { slice::from_raw_parts(self.inner.as_ptr(), self.file_capacity as _) }
     }
 
+    fn categories(&self) -> impl Iterator<Item = (u32, &'a FileHeader)> {
+        self.files()
+            .iter()
+            .enumerate()
+            .filter_map(|(i, file)| Some((i as u32, unsafe { file.as_ref()?.as_ref() })))
+    }
+
     fn files_mut(&mut self) -> &'a mut [Option<NonNull<FileHeader>>] {
         unsafe { slice::from_raw_parts_mut(self.inner.as_ptr(), self.file_capacity as _) }
     }
@@ -164,8 +1341,41 @@ impl fmt::Debug for FileHolder<'_> {
     }
 }
 
+/// Creates a repository without any versions or categories, detached from the game.
+///
+/// The static functions of [`MsgRepository`] always use the game's instance, so it is
+/// only useful as a fixture for code that takes a [`MsgRepository`] reference.
+impl Default for MsgRepository {
+    fn default() -> Self {
+        Self {
+            inner: FD4MessageManager {
+                _vtable: 0,
+                inner: NonNull::dangling(),
+                version_count: 0,
+                file_capacity: 0,
+                _unk18: 0,
+                _unk20: 0,
+                _unk28: 0,
+                alloc: DLStdAllocator::default(),
+            },
+            _unk38: 0,
+            _unk3c: 0,
+            _unk40: 0,
+            _unk44: 0,
+        }
+    }
+}
+
 impl FromSingleton for MsgRepository {}
 
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self, f)
+    }
+}
+
+impl error::Error for Error {}
+
 impl StaticPtr for MsgRepository {
     const STATIC_ID: windows::core::PCWSTR = w!("PMOD_MSG_REPOSITORY");
 }
@@ -173,3 +1383,52 @@ impl StaticPtr for MsgRepository {
 unsafe impl Send for MsgRepository {}
 
 unsafe impl Sync for MsgRepository {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_utf16_finds_unpaired_surrogates() {
+        let text: Vec<u16> = "Erdtree 🌳".encode_utf16().collect();
+
+        assert_eq!(validate_utf16(&[]), Ok(()));
+        assert_eq!(validate_utf16(&text), Ok(()));
+
+        assert_eq!(validate_utf16(&[0x41, 0xD800]), Err(1));
+        assert_eq!(validate_utf16(&[0xDC00, 0x41]), Err(0));
+        assert_eq!(validate_utf16(&[0xD83C, 0xDF33, 0x41, 0xDC00]), Err(3));
+    }
+
+    #[test]
+    fn sanitize_utf16_lossy_replaces_unpaired_surrogates() {
+        let text: Vec<u16> = "Erdtree 🌳".encode_utf16().collect();
+
+        assert_eq!(sanitize_utf16_lossy(&text), text);
+        assert_eq!(sanitize_utf16_lossy(&[0x41, 0xD800, 0x42]), [0x41, 0xFFFD, 0x42]);
+        assert_eq!(sanitize_utf16_lossy(&[0xDC00, 0xD800]), [0xFFFD, 0xFFFD]);
+    }
+
+    #[test]
+    fn encode_utf16_checked_rejects_nulls() {
+        assert_eq!(encode_utf16_checked("Ash"), Some(vec![0x41, 0x73, 0x68, 0]));
+        assert_eq!(encode_utf16_checked(""), Some(vec![0]));
+        assert_eq!(encode_utf16_checked("A\0B"), None);
+    }
+
+    #[test]
+    fn release_msg_frees_unreferenced_owned_msgs() {
+        let data = alloc_msg(&[0x41, 0x42]).unwrap();
+        assert!(is_owned_msg(data));
+
+        file::register_external_msg(data);
+        assert_eq!(unsafe { release_msg(Some(data)) }, Some(data));
+        assert!(is_owned_msg(data), "still referenced by a message");
+
+        unregister_external_msg(data);
+        assert_eq!(unsafe { release_msg(Some(data)) }, None);
+        assert!(!is_owned_msg(data));
+
+        assert_eq!(unsafe { release_msg(None) }, None);
+    }
+}
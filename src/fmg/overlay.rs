@@ -0,0 +1,57 @@
+//! Messages overlaid over message files by [`MsgRepository::set_overlay`].
+//!
+//! Overlay entries are owned by pmod and are not stored in the message files,
+//! so they are unaffected by the game reloading its message files.
+//!
+//! [`MsgRepository::set_overlay`]: super::MsgRepository::set_overlay
+
+use std::{
+    collections::BTreeMap,
+    ptr::NonNull,
+    sync::{Mutex, PoisonError},
+};
+
+/// Null terminated UTF-16 message data by version, category and id.
+///
+/// The data of an entry is boxed, so it does not move when the map is modified.
+type OverlayMap = BTreeMap<(u32, u32, u32), Box<[u16]>>;
+
+static OVERLAY: Mutex<OverlayMap> = Mutex::new(BTreeMap::new());
+
+/// Sets an overlay entry to `units`, which must not contain a null terminator.
+pub(super) fn set(version: u32, category: u32, id: u32, units: &[u16]) {
+    let mut data = Vec::with_capacity(units.len() + 1);
+    data.extend_from_slice(units);
+    data.push(0);
+
+    let mut overlay = OVERLAY.lock().unwrap_or_else(PoisonError::into_inner);
+    overlay.insert((version, category, id), data.into_boxed_slice());
+}
+
+/// Returns a pointer to the null terminated data of an overlay entry.
+///
+/// The pointer is valid until the entry is replaced, removed or cleared.
+pub(super) fn get(version: u32, category: u32, id: u32) -> Option<NonNull<u16>> {
+    let overlay = OVERLAY.lock().unwrap_or_else(PoisonError::into_inner);
+    let data = overlay.get(&(version, category, id))?;
+
+    NonNull::new(data.as_ptr() as *mut u16)
+}
+
+/// Decodes an overlay entry while holding the overlay lock.
+pub(super) fn get_string(version: u32, category: u32, id: u32) -> Option<String> {
+    let overlay = OVERLAY.lock().unwrap_or_else(PoisonError::into_inner);
+    let (_, units) = overlay.get(&(version, category, id))?.split_last()?;
+
+    Some(String::from_utf16_lossy(units))
+}
+
+pub(super) fn remove(version: u32, category: u32, id: u32) -> bool {
+    let mut overlay = OVERLAY.lock().unwrap_or_else(PoisonError::into_inner);
+    overlay.remove(&(version, category, id)).is_some()
+}
+
+pub(super) fn clear() {
+    let mut overlay = OVERLAY.lock().unwrap_or_else(PoisonError::into_inner);
+    overlay.clear();
+}
@@ -0,0 +1,208 @@
+//! Cooperative message id range reservations, see [`MsgRepository::reserve_id_range`].
+//!
+//! Reservations are stored in a named file mapping, so they are visible to every module
+//! in the process that uses pmod, each of which has its own copy of this module.
+//!
+//! [`MsgRepository::reserve_id_range`]: super::MsgRepository::reserve_id_range
+
+use std::{
+    fmt,
+    ops::Range,
+    ptr::{self, NonNull},
+    str,
+    sync::LazyLock,
+};
+
+use windows::{
+    core::w,
+    Win32::System::Threading::{AcquireSRWLockExclusive, ReleaseSRWLockExclusive, SRWLOCK},
+};
+
+use super::{Error, Result};
+use crate::static_lock::map_shared;
+
+/// The maximum number of reservations held at the same time by all modules.
+pub const MAX_RESERVATIONS: usize = 256;
+
+/// The maximum length of the name of a reservation owner in bytes.
+pub const MAX_OWNER_LEN: usize = 32;
+
+/// Name of the owner of a reservation, truncated to [`MAX_OWNER_LEN`] bytes.
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct ReservationOwner {
+    len: u8,
+    bytes: [u8; MAX_OWNER_LEN],
+}
+
+/// A reserved range of message ids, released when dropped.
+#[must_use]
+#[derive(Debug)]
+pub struct ReservationHandle {
+    slot: usize,
+    category: u32,
+    range: Range<u32>,
+}
+
+#[repr(C)]
+struct ReservationTable {
+    lock: SRWLOCK,
+    entries: [Reservation; MAX_RESERVATIONS],
+}
+
+/// A zeroed reservation with a `module` of 0 is unused.
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct Reservation {
+    module: usize,
+    category: u32,
+    start: u32,
+    end: u32,
+    owner: ReservationOwner,
+}
+
+struct SharedTable(NonNull<ReservationTable>);
+
+// SAFETY: the table lives in a named file mapping that is never unmapped,
+// and it is only accessed while holding its SRW lock.
+unsafe impl Send for SharedTable {}
+
+unsafe impl Sync for SharedTable {}
+
+// Starts zero-initialized, valid for SRWLOCK and unused reservations.
+static TABLE: LazyLock<SharedTable> =
+    LazyLock::new(|| SharedTable(unsafe { map_shared(w!("PMOD_MSG_RESERVATIONS")) }));
+
+/// Its address identifies the module that holds a reservation.
+static MODULE_TOKEN: u8 = 0;
+
+impl ReservationOwner {
+    fn new(name: &str) -> Self {
+        let mut len = name.len().min(MAX_OWNER_LEN);
+
+        while !name.is_char_boundary(len) {
+            len -= 1;
+        }
+
+        let mut bytes = [0; MAX_OWNER_LEN];
+        bytes[..len].copy_from_slice(&name.as_bytes()[..len]);
+
+        Self {
+            len: len as u8,
+            bytes,
+        }
+    }
+
+    /// Returns the name of the owner.
+    pub fn as_str(&self) -> &str {
+        // Other modules may have written anything to the shared table
+        let bytes = self.bytes.get(..self.len as usize).unwrap_or_default();
+        str::from_utf8(bytes).unwrap_or_default()
+    }
+}
+
+impl fmt::Debug for ReservationOwner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for ReservationOwner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl ReservationHandle {
+    /// Returns the category the ids are reserved in.
+    pub fn category(&self) -> u32 {
+        self.category
+    }
+
+    /// Returns the reserved range of ids.
+    pub fn range(&self) -> Range<u32> {
+        self.range.clone()
+    }
+}
+
+impl Drop for ReservationHandle {
+    fn drop(&mut self) {
+        with_entries(|entries| {
+            let entry = &mut entries[self.slot];
+
+            if entry.module == module_id()
+                && entry.category == self.category
+                && entry.start == self.range.start
+                && entry.end == self.range.end
+            {
+                entry.module = 0;
+            }
+        })
+    }
+}
+
+pub(super) fn reserve(category: u32, range: Range<u32>, owner: &str) -> Result<ReservationHandle> {
+    if range.start == 0 || range.is_empty() {
+        return Err(Error::InvalidRange);
+    }
+
+    with_entries(|entries| {
+        let overlapping = entries.iter().find(|e| {
+            e.module != 0 && e.category == category && e.start < range.end && range.start < e.end
+        });
+
+        if let Some(overlapping) = overlapping {
+            return Err(Error::RangeReserved(overlapping.owner));
+        }
+
+        let slot = entries
+            .iter()
+            .position(|e| e.module == 0)
+            .ok_or(Error::CapacityExceeded)?;
+
+        entries[slot] = Reservation {
+            module: module_id(),
+            category,
+            start: range.start,
+            end: range.end,
+            owner: ReservationOwner::new(owner),
+        };
+
+        Ok(ReservationHandle {
+            slot,
+            category,
+            range,
+        })
+    })
+}
+
+/// Returns the ranges reserved by this module in a category.
+pub(super) fn local_ranges(category: u32) -> Vec<Range<u32>> {
+    with_entries(|entries| {
+        entries
+            .iter()
+            .filter(|e| e.module == module_id() && e.category == category)
+            .map(|e| e.start..e.end)
+            .collect()
+    })
+}
+
+fn with_entries<R>(f: impl FnOnce(&mut [Reservation; MAX_RESERVATIONS]) -> R) -> R {
+    let table = TABLE.0.as_ptr();
+
+    unsafe {
+        let lock = ptr::addr_of_mut!((*table).lock);
+
+        AcquireSRWLockExclusive(lock);
+
+        let result = f(&mut *ptr::addr_of_mut!((*table).entries));
+
+        ReleaseSRWLockExclusive(lock);
+
+        result
+    }
+}
+
+fn module_id() -> usize {
+    ptr::addr_of!(MODULE_TOKEN) as usize
+}
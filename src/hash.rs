@@ -1,6 +1,17 @@
 //! Hash used by FromSoftware for resource names and file paths.
 
-use std::ffi::CStr;
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    ffi::{CStr, OsStr},
+    fmt,
+    hash::Hasher,
+    os::windows::ffi::OsStrExt,
+    path::Path,
+    sync::{LazyLock, Mutex, PoisonError},
+};
+
+use windows::core::PCWSTR;
 
 /// 32-bit non-cryptographic case insensitive hash
 /// used by FromSoftware for resource names and file paths.
@@ -9,39 +20,877 @@ pub trait DLHash {
     ///
     /// If two strings in lower case compare equal, their hashes must be equal.
     fn strhash(&self) -> u32;
+
+    /// Get the representation's hash wrapped for display, see [`DLHashDisplay`].
+    fn strhash_display(&self) -> DLHashDisplay {
+        DLHashDisplay(self.strhash())
+    }
 }
 
-impl DLHash for &str {
+/// A [`DLHash`] value that is displayed as `0x` followed by 8 lower case hex digits.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DLHashDisplay(pub u32);
+
+impl fmt::Display for DLHashDisplay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#010x}", self.0)
+    }
+}
+
+impl fmt::LowerHex for DLHashDisplay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::LowerHex::fmt(&self.0, f)
+    }
+}
+
+impl From<DLHashDisplay> for u32 {
+    fn from(value: DLHashDisplay) -> Self {
+        value.0
+    }
+}
+
+impl<T: DLHash + ?Sized> DLHash for &T {
     fn strhash(&self) -> u32 {
-        dl_hash(self.as_bytes().iter().copied())
+        (**self).strhash()
     }
 }
 
-impl DLHash for &CStr {
+impl DLHash for str {
     fn strhash(&self) -> u32 {
-        dl_hash(self.to_bytes().iter().copied())
+        dl_hash_bytes_fast(self.as_bytes())
     }
 }
 
-impl DLHash for &[u8] {
+impl DLHash for String {
     fn strhash(&self) -> u32 {
-        dl_hash(self.iter().copied())
+        self.as_str().strhash()
+    }
+}
+
+impl DLHash for Cow<'_, str> {
+    fn strhash(&self) -> u32 {
+        self.as_ref().strhash()
     }
 }
 
-impl DLHash for &[u16] {
+impl DLHash for CStr {
+    fn strhash(&self) -> u32 {
+        dl_hash_bytes_fast(self.to_bytes())
+    }
+}
+
+/// Hashed as UTF-16, like the wide paths used by the game.
+impl DLHash for OsStr {
+    fn strhash(&self) -> u32 {
+        dl_hash(self.encode_wide())
+    }
+}
+
+/// Hashed as UTF-16, like the wide paths used by the game.
+impl DLHash for Path {
+    fn strhash(&self) -> u32 {
+        self.as_os_str().strhash()
+    }
+}
+
+impl DLHash for [u8] {
+    fn strhash(&self) -> u32 {
+        dl_hash_bytes_fast(self)
+    }
+}
+
+impl DLHash for [u16] {
     fn strhash(&self) -> u32 {
         dl_hash(self.iter().copied())
     }
 }
 
-impl DLHash for &[u32] {
+impl DLHash for [u32] {
     fn strhash(&self) -> u32 {
         dl_hash(self.iter().copied())
     }
 }
 
+#[cfg(feature = "widestring")]
+impl DLHash for widestring::U16Str {
+    fn strhash(&self) -> u32 {
+        self.as_slice().strhash()
+    }
+}
+
+#[cfg(feature = "widestring")]
+impl DLHash for widestring::U16String {
+    fn strhash(&self) -> u32 {
+        self.as_slice().strhash()
+    }
+}
+
+/// 64-bit variant of [`DLHash`] used by newer FromSoftware resource systems,
+/// see [`dl_hash64_bytes`].
+pub trait DLHash64 {
+    /// Get the representation's 64-bit hash.
+    ///
+    /// If two strings in lower case compare equal, their hashes must be equal.
+    fn strhash64(&self) -> u64;
+}
+
+impl<T: DLHash64 + ?Sized> DLHash64 for &T {
+    fn strhash64(&self) -> u64 {
+        (**self).strhash64()
+    }
+}
+
+impl DLHash64 for str {
+    fn strhash64(&self) -> u64 {
+        dl_hash64(self.as_bytes().iter().copied())
+    }
+}
+
+impl DLHash64 for String {
+    fn strhash64(&self) -> u64 {
+        self.as_str().strhash64()
+    }
+}
+
+impl DLHash64 for Cow<'_, str> {
+    fn strhash64(&self) -> u64 {
+        self.as_ref().strhash64()
+    }
+}
+
+impl DLHash64 for CStr {
+    fn strhash64(&self) -> u64 {
+        dl_hash64(self.to_bytes().iter().copied())
+    }
+}
+
+/// Hashed as UTF-16, like the wide paths used by the game.
+impl DLHash64 for OsStr {
+    fn strhash64(&self) -> u64 {
+        dl_hash64(self.encode_wide())
+    }
+}
+
+/// Hashed as UTF-16, like the wide paths used by the game.
+impl DLHash64 for Path {
+    fn strhash64(&self) -> u64 {
+        self.as_os_str().strhash64()
+    }
+}
+
+impl DLHash64 for [u8] {
+    fn strhash64(&self) -> u64 {
+        dl_hash64(self.iter().copied())
+    }
+}
+
+impl DLHash64 for [u16] {
+    fn strhash64(&self) -> u64 {
+        dl_hash64(self.iter().copied())
+    }
+}
+
+impl DLHash64 for [u32] {
+    fn strhash64(&self) -> u64 {
+        dl_hash64(self.iter().copied())
+    }
+}
+
+#[cfg(feature = "widestring")]
+impl DLHash64 for widestring::U16Str {
+    fn strhash64(&self) -> u64 {
+        self.as_slice().strhash64()
+    }
+}
+
+#[cfg(feature = "widestring")]
+impl DLHash64 for widestring::U16String {
+    fn strhash64(&self) -> u64 {
+        self.as_slice().strhash64()
+    }
+}
+
+/// 32-bit non-cryptographic case sensitive variant of [`DLHash`],
+/// see [`dl_hash_case_sensitive`].
+pub trait DLHashCaseSensitive {
+    /// Get the representation's case sensitive hash.
+    ///
+    /// If two strings compare equal, their hashes must be equal.
+    fn strhash_case_sensitive(&self) -> u32;
+}
+
+impl<T: DLHashCaseSensitive + ?Sized> DLHashCaseSensitive for &T {
+    fn strhash_case_sensitive(&self) -> u32 {
+        (**self).strhash_case_sensitive()
+    }
+}
+
+impl DLHashCaseSensitive for str {
+    fn strhash_case_sensitive(&self) -> u32 {
+        dl_hash_case_sensitive(self.as_bytes().iter().copied())
+    }
+}
+
+impl DLHashCaseSensitive for String {
+    fn strhash_case_sensitive(&self) -> u32 {
+        self.as_str().strhash_case_sensitive()
+    }
+}
+
+impl DLHashCaseSensitive for Cow<'_, str> {
+    fn strhash_case_sensitive(&self) -> u32 {
+        self.as_ref().strhash_case_sensitive()
+    }
+}
+
+impl DLHashCaseSensitive for CStr {
+    fn strhash_case_sensitive(&self) -> u32 {
+        dl_hash_case_sensitive(self.to_bytes().iter().copied())
+    }
+}
+
+/// Hashed as UTF-16, like the wide paths used by the game.
+impl DLHashCaseSensitive for OsStr {
+    fn strhash_case_sensitive(&self) -> u32 {
+        dl_hash_case_sensitive(self.encode_wide())
+    }
+}
+
+/// Hashed as UTF-16, like the wide paths used by the game.
+impl DLHashCaseSensitive for Path {
+    fn strhash_case_sensitive(&self) -> u32 {
+        self.as_os_str().strhash_case_sensitive()
+    }
+}
+
+impl DLHashCaseSensitive for [u8] {
+    fn strhash_case_sensitive(&self) -> u32 {
+        dl_hash_case_sensitive(self.iter().copied())
+    }
+}
+
+impl DLHashCaseSensitive for [u16] {
+    fn strhash_case_sensitive(&self) -> u32 {
+        dl_hash_case_sensitive(self.iter().copied())
+    }
+}
+
+impl DLHashCaseSensitive for [u32] {
+    fn strhash_case_sensitive(&self) -> u32 {
+        dl_hash_case_sensitive(self.iter().copied())
+    }
+}
+
+#[cfg(feature = "widestring")]
+impl DLHashCaseSensitive for widestring::U16Str {
+    fn strhash_case_sensitive(&self) -> u32 {
+        self.as_slice().strhash_case_sensitive()
+    }
+}
+
+#[cfg(feature = "widestring")]
+impl DLHashCaseSensitive for widestring::U16String {
+    fn strhash_case_sensitive(&self) -> u32 {
+        self.as_slice().strhash_case_sensitive()
+    }
+}
+
+/// Hashes UTF-16 code units like [`DLHash`] as they are produced.
+pub fn dl_hash_iter<I>(i: I) -> u32
+where
+    I: IntoIterator<Item = u16>,
+{
+    dl_hash(i)
+}
+
+/// Hashes a null terminated wide string like [`DLHash`], reading at most `max_len`
+/// code units including the null terminator.
+///
+/// Returns [`None`] if `ptr` is null or if there is no null terminator
+/// within the first `max_len` code units.
+///
+/// # Safety
+/// `ptr` must be null or valid for reads up to and including its null terminator
+/// or up to `max_len` code units, whichever comes first.
+pub unsafe fn strhash_pcwstr(ptr: PCWSTR, max_len: usize) -> Option<u32> {
+    if ptr.is_null() {
+        return None;
+    }
+
+    let mut hasher = DLHasher::new();
+
+    for i in 0..max_len {
+        match ptr.0.add(i).read() {
+            0 => return Some(hasher.finish_u32()),
+            unit => hasher.write_char(unit as u32),
+        }
+    }
+
+    None
+}
+
+/// Converts a resource name or path to the canonical form the engine hashes it as,
+/// by converting ASCII letters to lower case and backslashes to slashes.
+///
+/// The hash of the result is equal to the hash of `s`. Duplicate separators
+/// are kept, since the engine hashes them as is.
+pub fn normalize_path(s: &str) -> String {
+    s.chars()
+        .map(|c| match c {
+            '\\' => '/',
+            c => c.to_ascii_lowercase(),
+        })
+        .collect()
+}
+
+/// Compares two resource names or paths with the same case and separator folding
+/// as [`DLHash`], without allocating, see [`normalize_path`].
+///
+/// Unlike comparing hashes, it has no false positives from hash collisions.
+pub fn eq_dlcase(a: &str, b: &str) -> bool {
+    a.len() == b.len() && eq_dlcase_units(a.bytes(), b.bytes())
+}
+
+/// Compares two resource names or paths the way the engine hashes them,
+/// see [`eq_dlcase`].
+pub fn eq_ignore_dlcase(a: &str, b: &str) -> bool {
+    eq_dlcase(a, b)
+}
+
+/// Returns `true` if the [`DLHash`] of `a` differs from the hash of `b`.
+pub fn verify_no_collision<A: DLHash, B: DLHash>(a: A, b: B) -> bool {
+    a.strhash() != b.strhash()
+}
+
+/// Finds all pairs of names in `names` with equal [`DLHash`] values.
+///
+/// Names equal by [`eq_dlcase`] are the same name to the engine and are not reported.
+pub fn debug_collisions<'a>(names: &[&'a str]) -> Vec<(&'a str, &'a str)> {
+    let mut hashed: Vec<_> = names.iter().map(|&name| (name.strhash(), name)).collect();
+    hashed.sort_unstable_by_key(|&(hash, _)| hash);
+
+    let mut collisions = Vec::new();
+
+    for group in hashed.chunk_by(|a, b| a.0 == b.0) {
+        for (i, &(_, a)) in group.iter().enumerate() {
+            for &(_, b) in &group[i + 1..] {
+                if !eq_dlcase(a, b) {
+                    collisions.push((a, b));
+                }
+            }
+        }
+    }
+
+    collisions
+}
+
+/// Compares two sequences of characters with the same folding as [`DLHash`].
+pub(crate) fn eq_dlcase_units<A, B>(a: A, b: B) -> bool
+where
+    A: IntoIterator<Item: Into<u32>>,
+    B: IntoIterator<Item: Into<u32>>,
+{
+    let a = a.into_iter().map(|ch| fold_char(ch.into()));
+    let b = b.into_iter().map(|ch| fold_char(ch.into()));
+
+    a.eq(b)
+}
+
+/// Process-wide registry of names by their [`DLHash`], for identifying hashes
+/// of unknown resources when debugging.
+///
+/// With the `known-names` feature, it is populated with the names of the param tables
+/// of the enabled game.
+pub struct HashRegistry;
+
+static HASH_REGISTRY: LazyLock<Mutex<BTreeMap<u32, &'static str>>> = LazyLock::new(|| {
+    #[cfg(feature = "known-names")]
+    let names = KNOWN_PARAM_NAMES.iter().map(|&name| (name.strhash(), name));
+
+    #[cfg(not(feature = "known-names"))]
+    let names = std::iter::empty();
+
+    Mutex::new(names.collect())
+});
+
+impl HashRegistry {
+    /// Registers a name by its hash.
+    ///
+    /// If another name with the same hash was registered first, it is kept.
+    /// Registered names are never freed.
+    pub fn register(name: &str) {
+        let mut registry = HASH_REGISTRY.lock().unwrap_or_else(PoisonError::into_inner);
+
+        registry
+            .entry(name.strhash())
+            .or_insert_with(|| name.to_owned().leak());
+    }
+
+    /// Registers every name of `names`, see [`HashRegistry::register`].
+    pub fn bulk_register<I>(names: I)
+    where
+        I: IntoIterator<Item: AsRef<str>>,
+    {
+        let mut registry = HASH_REGISTRY.lock().unwrap_or_else(PoisonError::into_inner);
+
+        for name in names {
+            let name = name.as_ref();
+
+            registry
+                .entry(name.strhash())
+                .or_insert_with(|| name.to_owned().leak());
+        }
+    }
+
+    /// Returns the registered name with the hash `hash`.
+    pub fn lookup(hash: u32) -> Option<&'static str> {
+        let registry = HASH_REGISTRY.lock().unwrap_or_else(PoisonError::into_inner);
+        registry.get(&hash).copied()
+    }
+}
+
+#[cfg(all(feature = "known-names", feature = "ds3", not(feature = "elden-ring")))]
+const KNOWN_PARAM_NAMES: &[&str] = &[
+    "AtkParam_Npc",
+    "AtkParam_Pc",
+    "BehaviorParam",
+    "BehaviorParam_PC",
+    "Bullet",
+    "CalcCorrectGraph",
+    "CharaInitParam",
+    "EquipMtrlSetParam",
+    "EquipParamAccessory",
+    "EquipParamGoods",
+    "EquipParamProtector",
+    "EquipParamWeapon",
+    "HitMtrlParam",
+    "ItemLotParam",
+    "LockCamParam",
+    "Magic",
+    "NpcParam",
+    "NpcThinkParam",
+    "ObjActParam",
+    "ReinforceParamProtector",
+    "ReinforceParamWeapon",
+    "ShopLineupParam",
+    "SpEffectParam",
+    "SwordArtsParam",
+    "ThrowParam",
+];
+
+#[cfg(all(
+    feature = "known-names",
+    feature = "sekiro",
+    not(feature = "ds3"),
+    not(feature = "elden-ring")
+))]
+const KNOWN_PARAM_NAMES: &[&str] = &[
+    "AtkParam_Npc",
+    "AtkParam_Pc",
+    "BehaviorParam",
+    "BehaviorParam_PC",
+    "Bullet",
+    "CalcCorrectGraph",
+    "CharaInitParam",
+    "EquipMtrlSetParam",
+    "EquipParamAccessory",
+    "EquipParamGoods",
+    "EquipParamProtector",
+    "EquipParamWeapon",
+    "HitMtrlParam",
+    "ItemLotParam",
+    "LockCamParam",
+    "NpcParam",
+    "NpcThinkParam",
+    "ObjActParam",
+    "ShopLineupParam",
+    "SpEffectParam",
+    "ThrowParam",
+];
+
+#[cfg(all(
+    feature = "known-names",
+    any(
+        all(not(feature = "ds3"), not(feature = "sekiro")),
+        feature = "elden-ring"
+    ),
+    not(feature = "ac6")
+))]
+const KNOWN_PARAM_NAMES: &[&str] = &[
+    "AtkParam_Npc",
+    "AtkParam_Pc",
+    "AttackElementCorrectParam",
+    "BehaviorParam",
+    "BehaviorParam_PC",
+    "BonfireWarpParam",
+    "Bullet",
+    "CalcCorrectGraph",
+    "CharaInitParam",
+    "EquipMtrlSetParam",
+    "EquipParamAccessory",
+    "EquipParamGem",
+    "EquipParamGoods",
+    "EquipParamProtector",
+    "EquipParamWeapon",
+    "GameAreaParam",
+    "HitMtrlParam",
+    "ItemLotParam_enemy",
+    "ItemLotParam_map",
+    "LockCamParam",
+    "Magic",
+    "NpcParam",
+    "NpcThinkParam",
+    "ObjActParam",
+    "ReinforceParamProtector",
+    "ReinforceParamWeapon",
+    "ShopLineupParam",
+    "ShopLineupParam_Recipe",
+    "SpEffectParam",
+    "SpEffectVfxParam",
+    "SwordArtsParam",
+    "ThrowParam",
+    "WorldMapPointParam",
+];
+
+#[cfg(all(feature = "known-names", feature = "ac6"))]
+const KNOWN_PARAM_NAMES: &[&str] = &[
+    "AtkParam_Npc",
+    "AtkParam_Pc",
+    "BehaviorParam",
+    "BehaviorParam_PC",
+    "Bullet",
+    "CalcCorrectGraph",
+    "CharaInitParam",
+    "EquipParamBooster",
+    "EquipParamFcs",
+    "EquipParamGenerator",
+    "EquipParamProtector",
+    "EquipParamWeapon",
+    "HitMtrlParam",
+    "LockCamParam",
+    "NpcParam",
+    "NpcThinkParam",
+    "SpEffectParam",
+    "ThrowParam",
+];
+
+/// Incremental [`DLHash`] hasher for data that is not contiguous.
+///
+/// Each byte or code unit written is hashed as a single character. It implements
+/// [`Hasher`], so `BuildHasherDefault<DLHasher>` can back a `HashMap`, but note that
+/// the [`Hash`] implementations of the standard library write more than the characters.
+///
+/// [`Hash`]: std::hash::Hash
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DLHasher(u32);
+
+impl DLHasher {
+    /// Creates a hasher in the state of an empty string.
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    /// Hashes a single character.
+    pub fn write_char(&mut self, ch: u32) {
+        self.0 = dl_hash_step(self.0, ch);
+    }
+
+    /// Hashes UTF-16 code units.
+    pub fn write_u16_slice(&mut self, units: &[u16]) {
+        for &unit in units {
+            self.write_char(unit as u32);
+        }
+    }
+
+    /// Hashes UTF-32 code points.
+    pub fn write_u32_slice(&mut self, chars: &[u32]) {
+        for &ch in chars {
+            self.write_char(ch);
+        }
+    }
+
+    /// Returns the hash of the characters written so far.
+    pub fn finish_u32(&self) -> u32 {
+        self.0
+    }
+}
+
+impl Hasher for DLHasher {
+    fn finish(&self) -> u64 {
+        self.0 as u64
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.write_char(byte as u32);
+        }
+    }
+}
+
+/// Hashes a string at compile time with [`dl_hash_bytes`],
+/// expanding to a `u32` constant.
+///
+/// Wrap the result in [`PrehashedName`] to look up resources by it.
+#[macro_export]
+macro_rules! dl_hash {
+    ($s:expr) => {
+        const { $crate::hash::dl_hash_bytes(str::as_bytes($s)) }
+    };
+}
+
+/// Hashes a string at compile time with [`dl_hash64_bytes`],
+/// expanding to a `u64` constant.
+#[macro_export]
+macro_rules! dl_hash64 {
+    ($s:expr) => {
+        const { $crate::hash::dl_hash64_bytes(str::as_bytes($s)) }
+    };
+}
+
+/// A precomputed [`DLHash`] value, e.g. from [`dl_hash!`](crate::dl_hash).
+///
+/// Its hash is the contained value.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PrehashedName(pub u32);
+
+impl DLHash for PrehashedName {
+    fn strhash(&self) -> u32 {
+        self.0
+    }
+}
+
+/// Hashes bytes like [`DLHash`] in a `const` context.
+///
+/// Since the hash is a polynomial in 137, four characters are hashed at a time
+/// with precomputed powers of 137, which shortens the dependency chain of the loop.
+/// The result is equal to [`dl_hash_bytes_scalar`].
+pub const fn dl_hash_bytes(bytes: &[u8]) -> u32 {
+    dl_hash_bytes_unrolled(0, bytes)
+}
+
+/// Hashes bytes like [`DLHash`] one character at a time.
+///
+/// This is the reference implementation that [`dl_hash_bytes`]
+/// and the SSE2 path of [`DLHash`] are tested against.
+pub const fn dl_hash_bytes_scalar(bytes: &[u8]) -> u32 {
+    let mut result = 0u32;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        result = dl_hash_step(result, bytes[i] as u32);
+
+        i += 1;
+    }
+
+    result
+}
+
+/// Hashes bytes like [`dl_hash_bytes`], 16 characters at a time with SSE2
+/// if the CPU supports it and there is at least one block of 16 characters.
+fn dl_hash_bytes_fast(bytes: &[u8]) -> u32 {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    if bytes.len() >= 16 && is_x86_feature_detected!("sse2") {
+        // SAFETY: SSE2 is supported
+        return unsafe { dl_hash_bytes_sse2(bytes) };
+    }
+
+    dl_hash_bytes(bytes)
+}
+
+/// Continues the hash `result` with `bytes`, four characters at a time.
+const fn dl_hash_bytes_unrolled(mut result: u32, bytes: &[u8]) -> u32 {
+    const P2: u32 = 137u32.pow(2);
+    const P3: u32 = 137u32.pow(3);
+    const P4: u32 = 137u32.pow(4);
+
+    let mut i = 0;
+
+    while i + 4 <= bytes.len() {
+        let c0 = fold_char(bytes[i] as u32).wrapping_mul(P3);
+        let c1 = fold_char(bytes[i + 1] as u32).wrapping_mul(P2);
+        let c2 = fold_char(bytes[i + 2] as u32).wrapping_mul(137);
+        let c3 = fold_char(bytes[i + 3] as u32);
+
+        result = result
+            .wrapping_mul(P4)
+            .wrapping_add(c0.wrapping_add(c1).wrapping_add(c2).wrapping_add(c3));
+
+        i += 4;
+    }
+
+    while i < bytes.len() {
+        result = dl_hash_step(result, bytes[i] as u32);
+
+        i += 1;
+    }
+
+    result
+}
+
+/// The powers of 137 of the characters of a 16 character block, split into 16-bit halves
+/// for `_mm_madd_epi16`, which multiplies signed 16-bit integers.
+///
+/// The high half is rounded up when the low half is negative, which cancels out
+/// the sign of the low half modulo 2^32.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+const SSE2_POWERS: ([i16; 16], [i16; 16]) = {
+    let mut low = [0; 16];
+    let mut high = [0; 16];
+
+    let mut power = 1u32;
+    let mut i = 16;
+
+    while i > 0 {
+        i -= 1;
+
+        low[i] = power as i16;
+        high[i] = (power.wrapping_add(0x8000) >> 16) as i16;
+
+        power = power.wrapping_mul(137);
+    }
+
+    (low, high)
+};
+
+/// Hashes bytes like [`dl_hash_bytes`], case folding and multiplying 16 characters
+/// at a time by their powers of 137.
+///
+/// # Safety
+/// The CPU must support SSE2.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "sse2")]
+unsafe fn dl_hash_bytes_sse2(bytes: &[u8]) -> u32 {
+    #[cfg(target_arch = "x86")]
+    use std::arch::x86::*;
+    #[cfg(target_arch = "x86_64")]
+    use std::arch::x86_64::*;
+
+    const P16: u32 = 137u32.wrapping_pow(16);
+
+    let (low, high) = &SSE2_POWERS;
+
+    let mut chunks = bytes.chunks_exact(16);
+    let mut result = 0u32;
+
+    unsafe {
+        let low = [
+            _mm_loadu_si128(low.as_ptr() as _),
+            _mm_loadu_si128(low.as_ptr().add(8) as _),
+        ];
+        let high = [
+            _mm_loadu_si128(high.as_ptr() as _),
+            _mm_loadu_si128(high.as_ptr().add(8) as _),
+        ];
+
+        let zero = _mm_setzero_si128();
+
+        for chunk in &mut chunks {
+            let chars = _mm_loadu_si128(chunk.as_ptr() as _);
+
+            // Moves 'A'..='Z' to the 26 smallest signed bytes
+            let shifted = _mm_add_epi8(chars, _mm_set1_epi8((0x80 - b'A') as i8));
+            let is_upper = _mm_cmplt_epi8(shifted, _mm_set1_epi8(i8::MIN + 26));
+            let is_backslash = _mm_cmpeq_epi8(chars, _mm_set1_epi8(b'\\' as i8));
+
+            let folded = _mm_xor_si128(
+                _mm_add_epi8(chars, _mm_and_si128(is_upper, _mm_set1_epi8(32))),
+                _mm_and_si128(is_backslash, _mm_set1_epi8((b'\\' ^ b'/') as i8)),
+            );
+
+            let folded = [
+                _mm_unpacklo_epi8(folded, zero),
+                _mm_unpackhi_epi8(folded, zero),
+            ];
+
+            let low_sum = _mm_add_epi32(
+                _mm_madd_epi16(folded[0], low[0]),
+                _mm_madd_epi16(folded[1], low[1]),
+            );
+            let high_sum = _mm_add_epi32(
+                _mm_madd_epi16(folded[0], high[0]),
+                _mm_madd_epi16(folded[1], high[1]),
+            );
+
+            let sum = _mm_add_epi32(low_sum, _mm_slli_epi32(high_sum, 16));
+            let sum = _mm_add_epi32(sum, _mm_shuffle_epi32(sum, 0b01_00_11_10));
+            let sum = _mm_add_epi32(sum, _mm_shuffle_epi32(sum, 0b10_11_00_01));
+
+            result = result
+                .wrapping_mul(P16)
+                .wrapping_add(_mm_cvtsi128_si32(sum) as u32);
+        }
+    }
+
+    dl_hash_bytes_unrolled(result, chunks.remainder())
+}
+
+/// Hashes bytes like [`DLHash64`] in a `const` context.
+///
+/// Characters are folded like [`dl_hash_bytes`], but accumulated into 64 bits
+/// with a multiplier of 133.
+pub const fn dl_hash64_bytes(bytes: &[u8]) -> u64 {
+    let mut result = 0u64;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        result = result.wrapping_mul(133);
+        result = result.wrapping_add(fold_char(bytes[i] as u32) as u64);
+
+        i += 1;
+    }
+
+    result
+}
+
 fn dl_hash<I>(i: I) -> u32
+where
+    I: IntoIterator<Item: Into<u32>>,
+{
+    let mut hasher = DLHasher::new();
+
+    for ch in i.into_iter() {
+        hasher.write_char(ch.into());
+    }
+
+    hasher.finish_u32()
+}
+
+const fn dl_hash_step(result: u32, ch: u32) -> u32 {
+    result.wrapping_mul(137).wrapping_add(fold_char(ch))
+}
+
+fn dl_hash64<I>(i: I) -> u64
+where
+    I: IntoIterator<Item: Into<u32>>,
+{
+    let mut result = 0u64;
+
+    for ch in i.into_iter() {
+        result = result.wrapping_mul(133);
+        result = result.wrapping_add(fold_char(ch.into()) as u64);
+    }
+
+    result
+}
+
+const fn fold_char(ch: u32) -> u32 {
+    if ch >= 'A' as u32 && ch <= 'Z' as u32 {
+        // To lowercase, other characters such as digits are left as is.
+        // Unverified against hashes from the engine, which may fold every character
+        // below 'Z' instead
+        ch + 32
+    } else if ch == '\\' as u32 {
+        // Treat backslashes as slashes
+        '/' as u32
+    } else {
+        ch
+    }
+}
+
+/// Hashes characters like [`DLHash`], but without converting them to lower case.
+///
+/// Backslashes are still treated as slashes.
+pub fn dl_hash_case_sensitive<I>(i: I) -> u32
 where
     I: IntoIterator<Item: Into<u32>>,
 {
@@ -50,10 +899,7 @@ where
     for ch in i.into_iter() {
         let mut ch = ch.into();
 
-        if ch <= 'Z' as u32 {
-            // To lowercase
-            ch += 32;
-        } else if ch == '\\' as u32 {
+        if ch == '\\' as u32 {
             // Treat backslashes as slashes
             ch = '/' as u32;
         }
@@ -64,3 +910,153 @@ where
 
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    macro_rules! assert_const_hash_eq {
+        ($($s:literal),* $(,)?) => {$(
+            assert_eq!(crate::dl_hash!($s), $s.strhash(), "{:?}", $s);
+            assert_eq!(PrehashedName(crate::dl_hash!($s)).strhash(), $s.strhash(), "{:?}", $s);
+        )*};
+    }
+
+    #[test]
+    fn const_hash_matches_runtime_hash() {
+        const WEAPON: PrehashedName = PrehashedName(crate::dl_hash!("EquipParamWeapon"));
+
+        assert_eq!(WEAPON.strhash(), "EquipParamWeapon".strhash());
+
+        assert_const_hash_eq!(
+            "",
+            "a",
+            "EquipParamWeapon",
+            "equipparamweapon",
+            "ItemLotParam_map",
+            "SpEffectParam",
+            "data0:/param/gameparam/gameparam.parambnd.dcx",
+            "chr\\c0000.anibnd.dcx",
+        );
+    }
+
+    /// Hashes computed by this implementation, not dumped from the game.
+    ///
+    /// They only pin the current hash against accidental changes. Whether the engine
+    /// folds only A-Z, which changes the hashes of names with digits and separators,
+    /// has not been verified against hashes from the engine.
+    const PINNED_HASHES: &[(&str, u32)] = &[
+        ("EquipParamWeapon", 0xfa03df67),
+        ("AtkParam_Pc", 0x294ddffb),
+        ("ItemLotParam_map", 0x6492625c),
+        ("SpEffectParam", 0x912521c9),
+        ("NpcParam", 0x77a8b1da),
+        ("BehaviorParam_PC", 0x49cdf7f3),
+        ("m10_00_00_00", 0xb49ec89b),
+        ("c0000", 0x26236903),
+        ("data0:/param/gameparam/gameparam.parambnd.dcx", 0xd2a820c0),
+        ("map\\m10_00_00_00\\m10_00_00_00.msb.dcx", 0x57d07a17),
+        ("chr/c4100.chrbnd.dcx", 0xe0903f68),
+        ("msg/engus/item.msgbnd.dcx", 0x5e0d5f5c),
+    ];
+
+    #[test]
+    fn pinned_hashes() {
+        for &(name, hash) in PINNED_HASHES {
+            assert_eq!(name.strhash(), hash, "{name:?}");
+            assert_eq!(name.to_ascii_uppercase().strhash(), hash, "{name:?}");
+            assert_eq!(name.replace('/', "\\").strhash(), hash, "{name:?}");
+        }
+    }
+
+    fn case_sensitive<T: DLHashCaseSensitive + ?Sized>(s: &T) -> u32 {
+        s.strhash_case_sensitive()
+    }
+
+    #[test]
+    fn case_sensitive_hash_accepts_unsized_and_owned() {
+        let name = "EquipParamWeapon";
+        let hash = dl_hash_case_sensitive(name.bytes());
+
+        assert_eq!(case_sensitive(name), hash);
+        assert_eq!(case_sensitive(&name), hash);
+        assert_eq!(case_sensitive(&name.to_owned()), hash);
+        assert_eq!(case_sensitive(name.as_bytes()), hash);
+        assert_eq!(case_sensitive(c"EquipParamWeapon"), hash);
+        assert_eq!(case_sensitive(Path::new(name)), hash);
+        assert_ne!(case_sensitive("equipparamweapon"), hash);
+    }
+
+    #[test]
+    fn fold_char_only_folds_ascii_letters_and_backslashes() {
+        for ch in 0..=0x10FFFF {
+            let expected = match char::from_u32(ch) {
+                Some(c @ 'A'..='Z') => c.to_ascii_lowercase() as u32,
+                Some('\\') => '/' as u32,
+                _ => ch,
+            };
+
+            assert_eq!(fold_char(ch), expected, "{ch:#x}");
+        }
+    }
+
+    #[test]
+    fn normalize_path_keeps_hash() {
+        let paths = PINNED_HASHES.iter().map(|&(name, _)| name).chain([
+            "",
+            "Data0:\\Param\\\\GameParam.parambnd.dcx",
+            "//server//Share\\",
+            "MSG/EngUS/Menu.msgbnd.dcx",
+        ]);
+
+        for path in paths {
+            let normalized = normalize_path(path);
+
+            assert_eq!(dl_hash(normalized.bytes()), path.strhash(), "{path:?}");
+            assert_eq!(normalized.len(), path.len(), "{path:?}");
+            assert!(eq_dlcase(&normalized, path), "{path:?}");
+            assert!(eq_ignore_dlcase(path, &normalized), "{path:?}");
+            assert!(!normalized.contains(|c: char| c.is_ascii_uppercase() || c == '\\'));
+        }
+    }
+
+    /// Mixed case letters, digits, separators and non-ASCII bytes,
+    /// including the bytes next to the folded ranges.
+    const HASH_ALPHABET: &[u8] = b"AaZz09_/\\.:@[`{\x7F\x80\xC0\xE0\xFF";
+
+    fn hash_bytes() -> impl Strategy<Value = Vec<u8>> {
+        prop_oneof![
+            prop::collection::vec(any::<u8>(), 0..256),
+            prop::collection::vec(prop::sample::select(HASH_ALPHABET), 0..64),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn fast_hashes_match_scalar_hash(bytes in hash_bytes()) {
+            let expected = dl_hash_bytes_scalar(&bytes);
+
+            prop_assert_eq!(dl_hash_bytes(&bytes), expected);
+            prop_assert_eq!(dl_hash_bytes_fast(&bytes), expected);
+            prop_assert_eq!(bytes.strhash(), expected);
+
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            if is_x86_feature_detected!("sse2") {
+                prop_assert_eq!(unsafe { dl_hash_bytes_sse2(&bytes) }, expected);
+            }
+        }
+
+        #[test]
+        fn scalar_hash_matches_hasher(bytes in hash_bytes()) {
+            let mut hasher = DLHasher::new();
+            hasher.write(&bytes);
+
+            let units: Vec<u16> = bytes.iter().map(|&b| b as u16).collect();
+
+            prop_assert_eq!(dl_hash_bytes_scalar(&bytes), hasher.finish_u32());
+            prop_assert_eq!(dl_hash_bytes_scalar(&bytes), units.strhash());
+        }
+    }
+}
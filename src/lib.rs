@@ -9,4 +9,4 @@ pub mod param;
 mod resource;
 mod static_lock;
 pub mod stdalloc;
-mod string;
+pub mod string;
@@ -43,13 +43,16 @@ pub struct FileHeader {
 }
 
 /// Possible param file manipulation errors.
+///
+/// New variants may be added in minor releases, so matches must have a wildcard arm.
+#[non_exhaustive]
 #[derive(Clone, Copy, Debug)]
 pub enum Error {
     /// The file format is improper for its encoding.
     Malformed,
 
-    /// Row id exceeds [`i32::MAX`], is negative.
-    NegativeId,
+    /// Row id exceeds [`i32::MAX`], is negative. Contains the id.
+    NegativeId(i32),
 
     /// Entry is not present.
     NotInTable,
@@ -60,6 +63,27 @@ pub enum Error {
 
     /// Could not reallocate file.
     FailedRealloc,
+
+    /// The file has more rows than can fit in a reallocated file.
+    TooManyRows,
+
+    /// A lookup table backup does not have the length of the lookup table,
+    /// or row data is longer than its row stride.
+    LengthMismatch,
+
+    /// The size of the row data is unknown, because the distances between rows differ,
+    /// see [`FileHeader::row_data_stride`].
+    UnknownRowSize,
+}
+
+/// The raw flag bytes of a param file, see [`FileHeader::flags`].
+///
+/// Its [`fmt::Binary`] implementation formats every byte in binary.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FileHeaderFlags {
+    pub endianness: u8,
+    pub layout_flags: u8,
+    pub format_flags: u8,
 }
 
 /// Param file manipulation result.
@@ -79,26 +103,57 @@ struct FileNameOffset {
     _unk14: [u32; 6],
 }
 
+/// A row descriptor of a param file that is not in large mode.
+///
+/// Offsets are relative to the start of the file.
 #[repr(C)]
-#[derive(Clone, Copy)]
-struct RowDescriptor12 {
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RowDescriptor12 {
+    /// The id of the row.
     pub id: u32,
+
+    /// The offset of the row data.
     pub data_offset: u32,
+
+    /// The offset of the row name.
     pub name_offset: u32,
 }
 
+/// A row descriptor of a param file in large mode, see [`FileHeader::is_large_mode`].
+///
+/// Offsets are relative to the start of the file.
 #[repr(C)]
-#[derive(Clone, Copy)]
-struct RowDescriptor24 {
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RowDescriptor24 {
+    /// The id of the row.
     pub id: u32,
+
+    /// The offset of the row data.
     pub data_offset: u64,
+
+    /// The offset of the row name.
     pub name_offset: u64,
 }
 
+/// A row descriptor of either layout.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RowDescriptor {
+    Small(RowDescriptor12),
+    Large(RowDescriptor24),
+}
+
+/// An entry of the param table lookup table, which is sorted by row id.
+///
+/// Entries of rows hold the index of their row descriptor. If the last entry has the id
+/// [`u32::MAX`], it holds the index of the first free entry, and each free entry holds
+/// the bitwise NOT of the index of the next free entry, with `!i32::MIN` marking the end.
 #[repr(C)]
-#[derive(Clone, Copy)]
-struct LutEntry {
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LutEntry {
+    /// The id of the row, or [`u32::MAX`] for the free list entry.
     pub id: u32,
+
+    /// The index of the row descriptor, or a free list index.
     pub index: i32,
 }
 
@@ -116,7 +171,7 @@ impl FileHeader {
             encoding_rs::UTF_16LE.decode(raw_name)
         };
 
-        is_err.then_some(name).ok_or(Error::Malformed)
+        (!is_err).then_some(name).ok_or(Error::Malformed)
     }
 
     /// The number of rows in the param table lookup table.
@@ -135,6 +190,178 @@ impl FileHeader {
         Ok(self.row_count as usize)
     }
 
+    /// The number of rows in the param table lookup table that hold data,
+    /// excluding free row slots and the free list entry.
+    ///
+    /// # Errors:
+    /// - [`Error::Malformed`] if the number of rows exceeds [`i32::MAX`]
+    ///   or if the free list is invalid.
+    pub fn live_row_count(&self) -> Result<usize> {
+        let has_free_list = self.lut().last().is_some_and(|e| e.id == u32::MAX);
+
+        Ok(self.row_count()? - has_free_list as usize - self.free_slot_count()?)
+    }
+
+    /// The number of free row slots that can be filled by [`FileHeader::insert_row`]
+    /// without reallocating.
+    ///
+    /// Files which have never been reallocated have no free slots.
+    ///
+    /// # Errors:
+    /// - [`Error::Malformed`] if the free list is invalid.
+    pub fn free_slot_count(&self) -> Result<usize> {
+        let Some((next, rest)) = self.lut().split_last().filter(|e| e.0.id == u32::MAX) else {
+            return Ok(0);
+        };
+
+        let mut count = 0;
+        let mut free_index = next.index;
+
+        // The end of the free list is marked by `!i32::MIN`
+        while free_index != !i32::MIN {
+            let entry = usize::try_from(free_index)
+                .ok()
+                .and_then(|i| rest.get(i))
+                .ok_or(Error::Malformed)?;
+
+            count += 1;
+
+            if count > rest.len() {
+                return Err(Error::Malformed);
+            }
+
+            free_index = !entry.index;
+        }
+
+        Ok(count)
+    }
+
+    /// Iterates over every entry of the param table lookup table,
+    /// including free entries and the free list entry.
+    pub fn iter_lut(&self) -> impl Iterator<Item = &LutEntry> {
+        self.lut().iter()
+    }
+
+    /// Iterates mutably over every entry of the param table lookup table,
+    /// including free entries and the free list entry.
+    ///
+    /// Entries must be kept sorted by id and the free list must be kept valid
+    /// for row manipulation to keep working.
+    pub fn iter_lut_mut(&mut self) -> impl Iterator<Item = &mut LutEntry> {
+        self.lut_mut().iter_mut()
+    }
+
+    /// Copies the lookup table, including the free list, so it can be restored
+    /// with [`FileHeader::restore_lut`].
+    ///
+    /// # Errors:
+    /// - [`Error::Malformed`] if the number of rows exceeds [`i32::MAX`].
+    pub fn backup_lut(&self) -> Result<Vec<LutEntry>> {
+        self.row_count()?;
+
+        Ok(self.lut().to_vec())
+    }
+
+    /// Restores the lookup table from a backup made with [`FileHeader::backup_lut`].
+    ///
+    /// Row descriptors are not restored, but rows inserted since the backup
+    /// are returned to the free list.
+    ///
+    /// # Errors:
+    /// - [`Error::Malformed`] if the number of rows exceeds [`i32::MAX`].
+    /// - [`Error::LengthMismatch`] if the length of `backup` differs from the lookup table,
+    ///   for example if the file was reallocated since.
+    pub fn restore_lut(&mut self, backup: &[LutEntry]) -> Result<()> {
+        self.row_count()?;
+
+        let lut = self.lut_mut();
+
+        if lut.len() != backup.len() {
+            return Err(Error::LengthMismatch);
+        }
+
+        lut.copy_from_slice(backup);
+
+        Ok(())
+    }
+
+    /// Overrides the number of rows in the param table lookup table.
+    ///
+    /// Both the row count before the file and the row count in the header are written,
+    /// the latter saturating at [`u16::MAX`] like in reallocated files.
+    ///
+    /// # Safety
+    /// `count` must not exceed the number of entries in the lookup table,
+    /// since it is used to bound every lookup.
+    pub unsafe fn set_row_count_hint(&mut self, count: i32) {
+        let count = count.max(0);
+
+        #[cfg(any(all(not(feature = "ds3"), not(feature = "sekiro")), feature = "elden-ring"))]
+        unsafe {
+            *(self.file_base().byte_sub(12) as *mut i32) = count;
+        }
+
+        self.row_count = Ord::min(count, u16::MAX as i32) as u16;
+    }
+
+    /// Recomputes the number of rows in the param table lookup table by walking it
+    /// while entry ids are strictly increasing, up to and including the free list entry.
+    ///
+    /// Only the current number of rows is walked, so a count that is too small
+    /// is not repaired, see [`FileHeader::repair_row_count_with_capacity`].
+    ///
+    /// Returns the new number of rows.
+    ///
+    /// # Errors:
+    /// - [`Error::Malformed`] if the number of rows exceeds the maximum row count.
+    pub fn repair_row_count(&mut self) -> Result<usize> {
+        let capacity = self.row_count()?;
+
+        // SAFETY: the lookup table holds at least the current number of rows
+        unsafe { self.repair_row_count_with_capacity(capacity) }
+    }
+
+    /// Recomputes the number of rows in the param table lookup table
+    /// like [`FileHeader::repair_row_count`], walking at most `capacity` entries.
+    ///
+    /// The capacity of the lookup table is not stored in the file, since it lies
+    /// after the end of the file, so it can't be derived from the header.
+    ///
+    /// # Errors:
+    /// - [`Error::Malformed`] if the number of rows exceeds the maximum row count.
+    ///
+    /// # Safety
+    /// The lookup table must be valid for reads of `capacity` entries.
+    pub unsafe fn repair_row_count_with_capacity(&mut self, capacity: usize) -> Result<usize> {
+        let lut = unsafe { self.raw_lut_with_len(capacity).as_ref() };
+
+        let mut count = 0;
+        let mut prev_id = None;
+
+        for entry in lut {
+            if prev_id.is_some_and(|prev_id| prev_id >= entry.id) {
+                break;
+            }
+
+            count += 1;
+            prev_id = Some(entry.id);
+
+            if entry.id == u32::MAX {
+                break;
+            }
+        }
+
+        if count > MAX_ROW_COUNT + 1 {
+            return Err(Error::Malformed);
+        }
+
+        unsafe {
+            self.set_row_count_hint(count as i32);
+        }
+
+        Ok(count)
+    }
+
     /// Searches for a row by its id with a binary search, returning a pointer to its data.
     ///
     /// `id` must be a non-negative signed 32-bit integer.
@@ -144,7 +371,7 @@ impl FileHeader {
     /// - [`Error::NotInTable`] if the corresponding row is not found.
     /// - [`Error::Malformed`] if param file can't be parsed.
     pub fn find_row(&self, id: i32) -> Result<NonNull<u8>> {
-        let id = u32::try_from(id).map_err(|_| Error::NegativeId)?;
+        let id = u32::try_from(id).map_err(|_| Error::NegativeId(id))?;
         let index = self.descriptor_index_by_id(id)?;
 
         unsafe {
@@ -167,6 +394,125 @@ impl FileHeader {
         }
     }
 
+    /// Checks whether a row with the id `id` exists, only searching the lookup table.
+    ///
+    /// # Errors:
+    /// - [`Error::NegativeId`] if `id` is negative.
+    pub fn contains_id(&self, id: i32) -> Result<bool> {
+        let id = u32::try_from(id).map_err(|_| Error::NegativeId(id))?;
+
+        // Free entries hold negative indices
+        Ok(find_lut_entry(self.lut(), id).is_some_and(|e| e.index >= 0))
+    }
+
+    /// Find a row descriptor by its row id.
+    ///
+    /// `id` must be a non-negative signed 32-bit integer.
+    ///
+    /// # Errors:
+    /// - [`Error::NegativeId`] if `id` is negative.
+    /// - [`Error::NotInTable`] if the corresponding row is not found.
+    /// - [`Error::Malformed`] if param file can't be parsed.
+    pub fn find_row_descriptor(&self, id: i32) -> Result<RowDescriptor> {
+        let id = u32::try_from(id).map_err(|_| Error::NegativeId(id))?;
+        let index = self.descriptor_index_by_id(id)?;
+
+        let descriptor = unsafe {
+            let descriptor_base = self.file_base().byte_add(self.row_descriptor_offset()?);
+
+            if self.is_large_mode() {
+                RowDescriptor::Large(*(descriptor_base as *const RowDescriptor24).add(index))
+            } else {
+                RowDescriptor::Small(*(descriptor_base as *const RowDescriptor12).add(index))
+            }
+        };
+
+        if descriptor.id() == id {
+            Ok(descriptor)
+        } else {
+            Err(Error::NotInTable)
+        }
+    }
+
+    /// The uniform distance in bytes between the data of consecutive rows,
+    /// in lookup table order.
+    ///
+    /// Returns [`None`] if the distances differ, are negative,
+    /// or if the table has less than two rows.
+    ///
+    /// # Errors:
+    /// - [`Error::Malformed`] if param file can't be parsed.
+    pub fn row_data_stride(&self) -> Result<Option<usize>> {
+        let descriptor_base = self.file_base().wrapping_byte_add(self.row_descriptor_offset()?);
+
+        let lut = self.lut();
+
+        let mut prev_offset = None;
+        let mut stride = None;
+
+        // Free entries hold negative indices
+        let indices = lut
+            .iter()
+            .filter(|e| e.id != u32::MAX)
+            .filter_map(|e| usize::try_from(e.index).ok());
+
+        for index in indices {
+            if index >= lut.len() {
+                return Err(Error::Malformed);
+            }
+
+            let data_offset = unsafe {
+                let descriptor: &dyn ReadRowDescriptor = if self.is_large_mode() {
+                    &*(descriptor_base as *const RowDescriptor24).add(index)
+                } else {
+                    &*(descriptor_base as *const RowDescriptor12).add(index)
+                };
+                descriptor.read_offset()
+            };
+
+            if let Some(prev_offset) = prev_offset.replace(data_offset) {
+                let Some(distance) = usize::checked_sub(data_offset, prev_offset) else {
+                    return Ok(None);
+                };
+
+                if *stride.get_or_insert(distance) != distance {
+                    return Ok(None);
+                }
+            }
+        }
+
+        Ok(stride)
+    }
+
+    /// The id of the row that [`FileHeader::insert_row`] would insert next.
+    ///
+    /// # Errors:
+    /// - [`Error::NeedsRealloc`] if insertion can only happen after a reallocation.
+    /// - [`Error::Malformed`] if the free list is invalid.
+    pub fn peek_next_free_id(&self) -> Result<i32> {
+        if !self.is_large_mode() {
+            return Err(Error::NeedsRealloc);
+        }
+
+        let (next, rest) = self
+            .lut()
+            .split_last()
+            .filter(|e| e.0.id == u32::MAX)
+            .ok_or(Error::NeedsRealloc)?;
+
+        // Empty free list
+        if next.index == !i32::MIN {
+            return Err(Error::NeedsRealloc);
+        }
+
+        let entry = usize::try_from(next.index)
+            .ok()
+            .and_then(|index| rest.get(index))
+            .ok_or(Error::Malformed)?;
+
+        i32::try_from(entry.id).map_err(|_| Error::Malformed)
+    }
+
     /// Tries to insert a new row with fields pointed to by `data`
     /// and returns its positive id.
     ///
@@ -216,7 +562,7 @@ impl FileHeader {
             return Err(Error::NeedsRealloc);
         }
 
-        let id = u32::try_from(id).map_err(|_| Error::NegativeId)?;
+        let id = u32::try_from(id).map_err(|_| Error::NegativeId(id))?;
         let index = self.descriptor_index_by_id(id)?;
 
         let file_base = self.file_base();
@@ -247,7 +593,7 @@ impl FileHeader {
             return Err(Error::NeedsRealloc);
         }
 
-        let id = u32::try_from(id).map_err(|_| Error::NegativeId)?;
+        let id = u32::try_from(id).map_err(|_| Error::NegativeId(id))?;
 
         let mut index = self.descriptor_index_by_id(id)?;
         index = push_free_lut_entry(self.lut_mut(), index)?;
@@ -288,6 +634,72 @@ impl FileHeader {
         self.layout_flags & 0x80 != 0
     }
 
+    /// Returns the raw flag bytes of the file, which can be formatted in binary.
+    pub fn flags(&self) -> FileHeaderFlags {
+        FileHeaderFlags {
+            endianness: self.endianness,
+            layout_flags: self.layout_flags,
+            format_flags: self.format_flags,
+        }
+    }
+
+    /// Returns the raw format flags byte, including flags without accessors.
+    pub fn format_flags_raw(&self) -> u8 {
+        self.format_flags
+    }
+
+    /// Returns the raw layout flags byte, including flags without accessors.
+    pub fn layout_flags_raw(&self) -> u8 {
+        self.layout_flags
+    }
+
+    /// Returns the param format version of the file.
+    ///
+    /// Reallocated files have a version of 1.
+    pub fn version(&self) -> u16 {
+        self.version
+    }
+
+    /// Sets the param format version of the file.
+    pub fn set_version(&mut self, version: u16) {
+        self.version = version;
+    }
+
+    /// Returns the raw offset of the row name strings from the start of the file.
+    pub fn strings_offset_raw(&self) -> u32 {
+        self.strings_offset
+    }
+
+    /// Sets the raw offset of the row name strings from the start of the file.
+    ///
+    /// The offset is not validated, and row names are located through their descriptors.
+    pub fn set_strings_offset(&mut self, offset: u32) {
+        self.strings_offset = offset;
+    }
+
+    /// Returns the raw offset of the row data from the start of the file,
+    /// or [`None`] if the layout of the file does not have the field.
+    pub fn data_offset_raw(&self) -> Option<u64> {
+        // Layouts below 3 do not have the `data_offset` field
+        (self.layout_flags > 2).then_some(self.data_offset)
+    }
+
+    /// Sets the raw offset of the row data from the start of the file.
+    ///
+    /// The offset is not validated, and row data is located through its descriptors.
+    ///
+    /// Returns `false` and has no effect if the layout of the file does not have the field,
+    /// whose bytes belong to the row descriptors instead.
+    pub fn set_data_offset(&mut self, offset: u64) -> bool {
+        if self.layout_flags <= 2 {
+            return false;
+        }
+
+        self.data_offset = offset;
+
+        true
+    }
+
     fn file_base(&self) -> *mut u8 {
         self as *const _ as _
     }
@@ -326,16 +738,33 @@ impl FileHeader {
     /// Clone and reallocate a file, removing duplicate rows and fixing anomalies.
     ///
     /// # Errors:
-    /// - [`Error::FailedRealloc`] if the allocator returned null or if the file
-    /// is too big to be reallocated.
+    /// - [`Error::FailedRealloc`] if the allocator returned null.
+    /// - [`Error::TooManyRows`] if the file has too many rows to be reallocated.
     pub fn clone_reallocate(&self, grow: bool) -> Result<(&'static mut Self, usize)> {
+        self.clone_reallocate_impl(grow, false)
+    }
+
+    /// Clone and reallocate a file like [`FileHeader::clone_reallocate`],
+    /// but sort its rows by id first.
+    ///
+    /// Repairs files with an unsorted lookup table, which can't be binary searched.
+    /// Of rows with the same id, the one that comes first in the lookup table is kept.
+    ///
+    /// # Errors:
+    /// - [`Error::FailedRealloc`] if the allocator returned null.
+    /// - [`Error::TooManyRows`] if the file has too many rows to be reallocated.
+    pub fn clone_sorted(&self, grow: bool) -> Result<(&'static mut Self, usize)> {
+        self.clone_reallocate_impl(grow, true)
+    }
+
+    fn clone_reallocate_impl(&self, grow: bool, sort: bool) -> Result<(&'static mut Self, usize)> {
         // Account for `u32::MAX` special entry
         let has_extra = self.lut().last().is_some_and(|e| e.id == u32::MAX);
 
         let old_len = self.row_count().unwrap_or(0) - has_extra as usize;
 
         if old_len > MAX_ROW_COUNT {
-            return Err(Error::FailedRealloc);
+            return Err(Error::TooManyRows);
         }
 
         let new_len = {
@@ -368,30 +797,7 @@ impl FileHeader {
 
         // SAFETY: `new_file_base` is properly aligned and not null
         unsafe {
-            // Layouts below 3 do not have the `data_offset` field
-            if self.layout_flags <= 2 {
-                let data_offset = if self.is_large_mode() {
-                    old_len * mem::size_of::<RowDescriptor24>()
-                } else {
-                    old_len * mem::size_of::<RowDescriptor12>()
-                };
-
-                *new_file_base.cast() = Self {
-                    data_offset: usize::wrapping_sub(
-                        old_file_base.wrapping_byte_add(data_offset) as _,
-                        new_file_base as _,
-                    ) as u64,
-                    ..Default::default()
-                };
-            } else {
-                *new_file_base.cast() = Self {
-                    data_offset: usize::wrapping_sub(
-                        old_file_base.wrapping_byte_add(self.data_offset as _) as _,
-                        new_file_base as _,
-                    ) as u64,
-                    ..Default::default()
-                };
-            }
+            *new_file_base.cast() = self.relocated_header(old_len, new_file_base);
         }
 
         let descriptor_offset = |index| unsafe {
@@ -426,7 +832,18 @@ impl FileHeader {
 
         let mut free_index = !i32::MIN;
 
-        for entry in &self.lut()[..old_len] {
+        let mut sorted_lut = Vec::new();
+
+        let old_lut = if sort {
+            sorted_lut.extend_from_slice(&self.lut()[..old_len]);
+            // Stable, so the first of rows with the same id is kept
+            sorted_lut.sort_by_key(|e| e.id);
+            &sorted_lut[..]
+        } else {
+            &self.lut()[..old_len]
+        };
+
+        for entry in old_lut {
             if entry.id == prev_id || entry.index as usize >= MAX_ROW_COUNT {
                 continue;
             }
@@ -501,6 +918,235 @@ impl FileHeader {
         Ok((new_file, new_size))
     }
 
+    /// Clone only the rows with the ids in `ids` into a new file.
+    ///
+    /// Ids which are not in the table are skipped, duplicate ids are cloned once.
+    /// The data of every row is copied into the new file, which does not depend on this file
+    /// afterwards. The size of the row data is the distance between consecutive rows,
+    /// see [`FileHeader::row_data_stride`].
+    ///
+    /// Returns the new file and its size, excluding the lookup table.
+    ///
+    /// # Errors:
+    /// - [`Error::NegativeId`] if any id is negative.
+    /// - [`Error::UnknownRowSize`] if the table has less than two rows
+    ///   or their data is not evenly spaced, for example after rows were inserted.
+    /// - [`Error::TooManyRows`] if the rows don't fit in a param file.
+    /// - [`Error::FailedRealloc`] if the allocator returned null.
+    /// - [`Error::Malformed`] if param file can't be parsed.
+    pub fn clone_select(&self, ids: &[i32]) -> Result<(&'static mut Self, usize)> {
+        // Rows are only known to be followed by `row_stride` bytes of row data
+        // if the data of all rows is evenly spaced
+        let row_stride = self.row_data_stride()?.ok_or(Error::UnknownRowSize)?;
+
+        let old_file_base = self.file_base();
+        let old_descriptor_base = old_file_base.wrapping_byte_add(self.row_descriptor_offset()?);
+
+        let lut = self.lut();
+
+        let mut rows = Vec::with_capacity(ids.len());
+
+        for &id in ids {
+            let id = u32::try_from(id).map_err(|_| Error::NegativeId(id))?;
+
+            let Some(entry) = find_lut_entry(lut, id) else {
+                continue;
+            };
+
+            // Negative indices belong to free list entries
+            let Ok(index) = usize::try_from(entry.index) else {
+                continue;
+            };
+
+            if index >= lut.len() {
+                return Err(Error::Malformed);
+            }
+
+            let data_offset = unsafe {
+                let descriptor: &dyn ReadRowDescriptor = if self.is_large_mode() {
+                    &*(old_descriptor_base as *const RowDescriptor24).add(index)
+                } else {
+                    &*(old_descriptor_base as *const RowDescriptor12).add(index)
+                };
+                descriptor.read_offset()
+            };
+
+            rows.push((id, old_file_base.wrapping_byte_add(data_offset)));
+        }
+
+        rows.sort_unstable_by_key(|&(id, _)| id);
+        rows.dedup_by_key(|&mut (id, _)| id);
+
+        let rows = rows.iter().map(|&(id, data)| (id, data as *const u8, row_stride));
+
+        // SAFETY: the data of every row is followed by `row_stride` bytes of row data
+        unsafe { Self::alloc_packed(rows, row_stride) }
+    }
+
+    /// Allocate a new file holding `rows`, each given as its id and data.
+    ///
+    /// Row data is packed sequentially `row_stride` bytes apart and zero-padded to it.
+    /// Rows are sorted by id, and of rows with the same id, the first one is kept.
+    /// The file has no name and its free list is empty, see [`FileHeader::clone_reallocate`].
+    ///
+    /// Returns the new file and its size, excluding the lookup table.
+    ///
+    /// # Errors:
+    /// - [`Error::NegativeId`] if any id is negative.
+    /// - [`Error::LengthMismatch`] if the data of any row is longer than `row_stride`.
+    /// - [`Error::TooManyRows`] if the rows don't fit in a param file.
+    /// - [`Error::FailedRealloc`] if the allocator returned null.
+    pub fn rebuild_from_rows(
+        rows: &[(i32, &[u8])],
+        row_stride: usize,
+    ) -> Result<(&'static mut Self, usize)> {
+        let mut sorted = Vec::with_capacity(rows.len());
+
+        for &(id, data) in rows {
+            let id = u32::try_from(id).map_err(|_| Error::NegativeId(id))?;
+
+            if data.len() > row_stride {
+                return Err(Error::LengthMismatch);
+            }
+
+            sorted.push((id, data));
+        }
+
+        // Stable, so the first of rows with the same id is kept
+        sorted.sort_by_key(|&(id, _)| id);
+        sorted.dedup_by_key(|&mut (id, _)| id);
+
+        let rows = sorted.iter().map(|&(id, data)| (id, data.as_ptr(), data.len()));
+
+        // SAFETY: no row data is longer than `row_stride`
+        unsafe { Self::alloc_packed(rows, row_stride) }
+    }
+
+    /// Allocate a new file holding `rows`, each given as its id, data and data length,
+    /// sorted by id without duplicates.
+    ///
+    /// Row data is packed sequentially `row_stride` bytes apart and zero-padded to it.
+    /// The file has no name and its free list is empty.
+    ///
+    /// Returns the new file and its size, excluding the lookup table.
+    ///
+    /// # Errors:
+    /// - [`Error::TooManyRows`] if the rows don't fit in a param file.
+    /// - [`Error::FailedRealloc`] if the allocator returned null.
+    ///
+    /// # Safety
+    /// The data of every row must be valid for reads of its length,
+    /// which must not exceed `row_stride`.
+    unsafe fn alloc_packed<I>(rows: I, row_stride: usize) -> Result<(&'static mut Self, usize)>
+    where
+        I: ExactSizeIterator<Item = (u32, *const u8, usize)>,
+    {
+        let new_len = rows.len();
+
+        if new_len > MAX_ROW_COUNT {
+            return Err(Error::TooManyRows);
+        }
+
+        let data_start = (mem::size_of::<Self>() + new_len * mem::size_of::<RowDescriptor24>())
+            .next_multiple_of(16);
+
+        let new_size = new_len
+            .checked_mul(row_stride)
+            .and_then(|size| size.checked_add(data_start))
+            .filter(|&size| size <= i32::MAX as usize)
+            .ok_or(Error::TooManyRows)?;
+
+        let new_lut_offset = new_size.next_multiple_of(16);
+        let new_lut_size = (new_len + 1) * mem::size_of::<LutEntry>();
+
+        let new_file_base = unsafe {
+            let new_file_base = DLStdAllocator::default().alloc_zeroed(
+                Layout::from_size_align_unchecked(0x10 + new_lut_offset + new_lut_size, 16),
+            );
+
+            if new_file_base.is_null() {
+                return Err(Error::FailedRealloc);
+            }
+
+            new_file_base.byte_add(0x10)
+        };
+
+        // SAFETY: `new_file_base` is properly aligned, not null and large enough
+        // for the header, `new_len` descriptors, `new_len` rows of `row_stride` bytes
+        // and `new_len + 1` lookup table entries
+        unsafe {
+            *new_file_base.cast() = Self {
+                data_offset: data_start as u64,
+                ..Default::default()
+            };
+
+            let new_descriptors = slice::from_raw_parts_mut(
+                new_file_base.byte_add(0x40) as *mut RowDescriptor24,
+                new_len,
+            );
+
+            let new_lut = slice::from_raw_parts_mut(
+                new_file_base.byte_add(new_lut_offset) as *mut LutEntry,
+                new_len + 1,
+            );
+
+            for (i, (id, data, len)) in rows.enumerate().take(new_len) {
+                let data_offset = data_start + i * row_stride;
+
+                new_file_base
+                    .byte_add(data_offset)
+                    .copy_from_nonoverlapping(data, len);
+
+                new_descriptors[i] = RowDescriptor24 {
+                    id,
+                    data_offset: data_offset as u64,
+                    name_offset: 0,
+                };
+
+                new_lut[i] = LutEntry {
+                    id,
+                    index: i as i32,
+                };
+            }
+
+            // Empty free list
+            new_lut[new_len] = LutEntry {
+                id: u32::MAX,
+                index: !i32::MIN,
+            };
+
+            *new_file_base.byte_sub(16).cast() = new_size as i32;
+            *new_file_base.byte_sub(12).cast() = (new_len + 1) as i32;
+        }
+
+        let new_file = unsafe { &mut *(new_file_base as *mut FileHeader) };
+
+        new_file.row_count = Ord::min(new_len + 1, u16::MAX as usize) as u16;
+
+        Ok((new_file, new_size))
+    }
+
+    /// A default header for a reallocated copy of this file at `new_file_base`,
+    /// with its `data_offset` pointing to the row data of this file.
+    fn relocated_header(&self, old_len: usize, new_file_base: *mut u8) -> Self {
+        // Layouts below 3 do not have the `data_offset` field
+        let data_offset = if self.layout_flags > 2 {
+            self.data_offset as usize
+        } else if self.is_large_mode() {
+            old_len * mem::size_of::<RowDescriptor24>()
+        } else {
+            old_len * mem::size_of::<RowDescriptor12>()
+        };
+
+        Self {
+            data_offset: usize::wrapping_sub(
+                self.file_base().wrapping_byte_add(data_offset) as _,
+                new_file_base as _,
+            ) as u64,
+            ..Default::default()
+        }
+    }
+
     unsafe fn raw_name(&self) -> &[u8] {
         let utf16_name = self.is_utf16() && self.is_new_layout();
 
@@ -532,11 +1178,14 @@ impl FileHeader {
     }
 
     unsafe fn raw_lut(&self) -> NonNull<[LutEntry]> {
+        unsafe { self.raw_lut_with_len(self.row_count().ok().unwrap_or(0)) }
+    }
+
+    unsafe fn raw_lut_with_len(&self, len: usize) -> NonNull<[LutEntry]> {
         let file_base = self.file_base() as *const i32;
 
         if let Ok(offset) = usize::try_from(file_base.byte_sub(16).read_unaligned()) {
             let aligned_offset = offset.wrapping_add(15) & usize::wrapping_neg(16);
-            let len = self.row_count().ok().unwrap_or(0);
 
             NonNull::slice_from_raw_parts(
                 unsafe { NonNull::new_unchecked(file_base.byte_add(aligned_offset) as _) },
@@ -605,6 +1254,32 @@ fn pop_free_lut_entry<'a>(lut: &'a mut [LutEntry]) -> Result<&'a mut LutEntry> {
     Ok(popped)
 }
 
+impl RowDescriptor {
+    /// The id of the row.
+    pub fn id(&self) -> u32 {
+        match self {
+            Self::Small(d) => d.id,
+            Self::Large(d) => d.id,
+        }
+    }
+
+    /// The offset of the row data from the start of the file.
+    pub fn data_offset(&self) -> u64 {
+        match self {
+            Self::Small(d) => d.data_offset as _,
+            Self::Large(d) => d.data_offset,
+        }
+    }
+
+    /// The offset of the row name from the start of the file.
+    pub fn name_offset(&self) -> u64 {
+        match self {
+            Self::Small(d) => d.name_offset as _,
+            Self::Large(d) => d.name_offset,
+        }
+    }
+}
+
 trait ReadRowDescriptor {
     fn read(&self) -> (u32, usize);
     fn read_offset(&self) -> usize;
@@ -634,6 +1309,16 @@ impl ReadRowDescriptor for RowDescriptor24 {
     }
 }
 
+impl fmt::Binary for FileHeaderFlags {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "endianness: {:#010b}, layout_flags: {:#010b}, format_flags: {:#010b}",
+            self.endianness, self.layout_flags, self.format_flags
+        )
+    }
+}
+
 impl Default for FileHeader {
     fn default() -> Self {
         Self {
@@ -656,6 +1341,18 @@ impl Default for FileHeader {
     }
 }
 
+impl TryFrom<&FileHeader> for String {
+    type Error = Error;
+
+    /// Get the name of the param table as an owned string.
+    ///
+    /// # Errors:
+    /// - [`Error::Malformed`] if the name is not valid UTF-16/SJIS.
+    fn try_from(value: &FileHeader) -> Result<Self> {
+        value.name().map(Cow::into_owned)
+    }
+}
+
 impl Default for FileNameOffset {
     fn default() -> Self {
         Self {
@@ -673,3 +1370,169 @@ impl fmt::Display for Error {
 }
 
 impl error::Error for Error {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FREE_LIST_END: i32 = !i32::MIN;
+
+    fn test_lut(ids: &[u32], free_list: Option<i32>) -> Vec<LutEntry> {
+        let entries = ids.iter().enumerate().map(|(index, &id)| LutEntry {
+            id,
+            index: index as i32,
+        });
+
+        let next = free_list.map(|index| LutEntry {
+            id: u32::MAX,
+            index,
+        });
+
+        entries.chain(next).collect()
+    }
+
+    #[test]
+    fn free_list_needs_realloc() {
+        let mut lut = test_lut(&[10, 20], None);
+
+        assert!(matches!(
+            push_free_lut_entry(&mut lut, 0),
+            Err(Error::NeedsRealloc)
+        ));
+        assert!(matches!(
+            pop_free_lut_entry(&mut lut),
+            Err(Error::NeedsRealloc)
+        ));
+        assert_eq!(lut, test_lut(&[10, 20], None));
+    }
+
+    #[test]
+    fn free_list_is_last_in_first_out() {
+        let mut lut = test_lut(&[10, 20, 30], Some(FREE_LIST_END));
+
+        assert_eq!(push_free_lut_entry(&mut lut, 1).ok(), Some(1));
+        assert_eq!(push_free_lut_entry(&mut lut, 0).ok(), Some(0));
+
+        // The free list entry points at the last pushed entry,
+        // which holds the binary NOT of the next one
+        assert_eq!(lut[3].index, 0);
+        assert_eq!(lut[0].index, !1);
+        assert_eq!(lut[1].index, !FREE_LIST_END);
+
+        assert_eq!(
+            pop_free_lut_entry(&mut lut).ok().copied(),
+            Some(LutEntry { id: 10, index: 0 })
+        );
+        assert_eq!(
+            pop_free_lut_entry(&mut lut).ok().copied(),
+            Some(LutEntry { id: 20, index: 1 })
+        );
+
+        assert_eq!(lut, test_lut(&[10, 20, 30], Some(FREE_LIST_END)));
+        assert!(matches!(
+            pop_free_lut_entry(&mut lut),
+            Err(Error::NotInTable)
+        ));
+    }
+
+    #[test]
+    fn push_free_lut_entry_out_of_bounds() {
+        let mut lut = test_lut(&[10], Some(FREE_LIST_END));
+
+        // The free list entry itself can't be pushed
+        assert!(matches!(
+            push_free_lut_entry(&mut lut, 1),
+            Err(Error::NotInTable)
+        ));
+        assert_eq!(lut, test_lut(&[10], Some(FREE_LIST_END)));
+    }
+
+    #[test]
+    fn find_lut_entry_by_id() {
+        let lut = test_lut(&[10, 20, 30], Some(FREE_LIST_END));
+        let (_, rows) = lut.split_last().unwrap();
+
+        assert_eq!(
+            find_lut_entry(rows, 20),
+            Some(&LutEntry { id: 20, index: 1 })
+        );
+        assert_eq!(find_lut_entry(rows, 25), None);
+        assert_eq!(find_lut_entry(rows, 0), None);
+        assert_eq!(find_lut_entry(&[], 10), None);
+    }
+
+    fn row_data(file: &FileHeader, id: i32, len: usize) -> &[u8] {
+        let data = file.find_row(id).unwrap();
+        unsafe { slice::from_raw_parts(data.as_ptr(), len) }
+    }
+
+    #[test]
+    fn clone_select_copies_rows() {
+        let rows: [(i32, &[u8]); 3] = [(10, &[1; 8]), (20, &[2; 8]), (30, &[3; 8])];
+        let (file, _) = FileHeader::rebuild_from_rows(&rows, 8).unwrap();
+
+        let (clone, _) = file.clone_select(&[30, 10, 10, 99]).unwrap();
+
+        assert_eq!(clone.row_count().ok(), Some(3));
+        assert_eq!(clone.live_row_count().ok(), Some(2));
+        assert_eq!(clone.row_data_stride().ok(), Some(Some(8)));
+        assert_eq!(row_data(clone, 10, 8), [1; 8]);
+        assert_eq!(row_data(clone, 30, 8), [3; 8]);
+        assert!(matches!(clone.find_row(20), Err(Error::NotInTable)));
+
+        // The clone does not reference the data of the original file
+        assert_ne!(clone.find_row(10).ok(), file.find_row(10).ok());
+    }
+
+    #[test]
+    fn clone_select_needs_row_size() {
+        let rows: [(i32, &[u8]); 1] = [(10, &[1; 8])];
+        let (file, _) = FileHeader::rebuild_from_rows(&rows, 8).unwrap();
+
+        assert!(matches!(
+            file.clone_select(&[10]),
+            Err(Error::UnknownRowSize)
+        ));
+    }
+
+    #[test]
+    fn rebuild_from_rows_pads_and_dedups() {
+        let rows: [(i32, &[u8]); 3] = [(20, &[2; 4]), (10, &[1; 8]), (20, &[3; 8])];
+        let (file, size) = FileHeader::rebuild_from_rows(&rows, 8).unwrap();
+
+        assert_eq!(size, 0x40 + 2 * mem::size_of::<RowDescriptor24>() + 2 * 8);
+        assert_eq!(file.live_row_count().ok(), Some(2));
+        assert_eq!(file.free_slot_count().ok(), Some(0));
+        assert_eq!(row_data(file, 10, 8), [1; 8]);
+        assert_eq!(row_data(file, 20, 8), [2, 2, 2, 2, 0, 0, 0, 0]);
+
+        assert!(matches!(
+            FileHeader::rebuild_from_rows(&[(10, &[0; 9])], 8),
+            Err(Error::LengthMismatch)
+        ));
+        assert!(matches!(
+            FileHeader::rebuild_from_rows(&[(-1, &[])], 8),
+            Err(Error::NegativeId(-1))
+        ));
+    }
+
+    #[test]
+    fn repair_row_count_walks_lut() {
+        let rows: [(i32, &[u8]); 2] = [(10, &[1; 8]), (20, &[2; 8])];
+        let (file, _) = FileHeader::rebuild_from_rows(&rows, 8).unwrap();
+
+        assert_eq!(file.repair_row_count().ok(), Some(3));
+
+        unsafe { file.set_row_count_hint(1) };
+
+        assert_eq!(file.row_count().ok(), Some(1));
+        assert_eq!(file.row_count, 1);
+
+        // Too small counts can only be repaired with the capacity of the lookup table
+        assert_eq!(file.repair_row_count().ok(), Some(1));
+        assert_eq!(unsafe { file.repair_row_count_with_capacity(3) }.ok(), Some(3));
+        assert_eq!(file.row_count().ok(), Some(3));
+        assert_eq!(file.row_count, 3);
+        assert_eq!(file.live_row_count().ok(), Some(2));
+    }
+}
@@ -1,25 +1,35 @@
 //! Param row manipulation utilities.
 //!
-//! - Retrieve with [`ParamRepository::get_row`]
+//! - Retrieve with [`ParamRepository::get_row`] or [`ParamRepository::get_row_typed`]
 //! - Insert with [`ParamRepository::insert_row`]
 //! - Replace with [`ParamRepository::replace_row`]
 //! - Delete with [`ParamRepository::delete_row`]
+//! - Summarize with [`ParamRepository::debug_dump`]
 //! 
 //! Param row manipulation uses a free list approach with
 //! amortized O(1) insertion and removal performance.
 //! 
 //! Original implementation idea by tremwil.
 
-use std::{borrow::Cow, error, fmt, ptr::NonNull, sync::LazyLock};
+use std::{
+    alloc::{GlobalAlloc, Layout},
+    borrow::Cow,
+    collections::BTreeSet,
+    error, fmt, mem,
+    ops::{Deref, DerefMut},
+    ptr::NonNull,
+    slice,
+    sync::{LazyLock, Mutex, PoisonError},
+};
 
 use file::FileHeader;
 use from_singleton::FromSingleton;
 use windows::core::w;
 
 use crate::{
-    hash::DLHash,
+    hash::{DLHash, DLHashDisplay, HashRegistry, PrehashedName},
     resource::{ResCap, ResCapHolderItem, ResRep},
-    static_lock::{StaticLock, StaticPtr},
+    static_lock::{StaticLock, StaticLockWriteGuard, StaticPtr},
     stdalloc::DLStdAllocator,
 };
 
@@ -42,7 +52,19 @@ pub struct ParamResCap {
     file: NonNull<FileHeader>,
 }
 
+/// Exclusive access to the file of a param table, see [`ParamRepository::file_locked`].
+///
+/// Holds the write lock of the static [`ParamRepository`] until it is dropped,
+/// so the file can't be accessed past the lock scope.
+pub struct ParamFileMut {
+    _repo: StaticLockWriteGuard<'static, ParamRepository>,
+    table: NonNull<ParamResCap>,
+}
+
 /// Possible param manipulation errors.
+///
+/// New variants may be added in minor releases, so matches must have a wildcard arm.
+#[non_exhaustive]
 #[derive(Clone, Copy, Debug)]
 pub enum Error {
     /// Mismatch in input or file format.
@@ -51,8 +73,27 @@ pub enum Error {
     /// Static [`ParamRepository`] instance is null.
     NullInstance,
 
-    /// The param table with the specified `DLHash` does not exist.
-    TableNotFound,
+    /// The param table with the contained `DLHash` does not exist.
+    TableNotFound(u32),
+
+    /// Row data is not aligned for the requested row type.
+    MisalignedRow,
+
+    /// The param table was not registered by pmod, see [`ParamRepository::unregister_table`].
+    ForeignTable,
+
+    /// A param table with the contained `DLHash` already exists.
+    DuplicateTable(u32),
+
+    /// The repository has no param table to copy virtual function tables from,
+    /// see [`ParamRepository::register_table`].
+    EmptyRepository,
+
+    /// The param table name could not be encoded.
+    InvalidName,
+
+    /// Allocating a new param table failed.
+    FailedAlloc,
 }
 
 /// Param manipulation result.
@@ -61,7 +102,18 @@ pub type Result<T> = std::result::Result<T, Error>;
 static PARAM_REPOSITORY: LazyLock<StaticLock<ParamRepository>> =
     LazyLock::new(|| StaticLock::new());
 
+/// Param tables registered by pmod, see [`is_owned_table`].
+static OWNED_TABLES: Mutex<BTreeSet<usize>> = Mutex::new(BTreeSet::new());
+
 impl ParamRepository {
+    /// Returns whether the static [`ParamRepository`] instance has been created by the game,
+    /// without locking it.
+    ///
+    /// Param manipulation fails with [`Error::NullInstance`] until it has.
+    pub fn is_initialized() -> bool {
+        PARAM_REPOSITORY.is_initialized()
+    }
+
     /// Finds a param table [`ParamResCap`] by its `DLHash`.
     ///
     /// # Errors:
@@ -78,6 +130,24 @@ impl ParamRepository {
         self.raw_find_table(s).map(|mut t| unsafe { t.as_mut() })
     }
 
+    /// Finds a param table [`ParamResCap`] by its name, which is compared like its `DLHash`
+    /// but without false positives from hash collisions.
+    ///
+    /// # Errors:
+    /// - [`Error::TableNotFound`]
+    pub fn find_table_by_name<'a>(&'a self, name: &str) -> Result<&'a ParamResCap> {
+        self.raw_find_table_by_name(name).map(|t| unsafe { t.as_ref() })
+    }
+
+    /// Finds a param table [`ParamResCap`] by its name, which is compared like its `DLHash`
+    /// but without false positives from hash collisions.
+    ///
+    /// # Errors:
+    /// - [`Error::TableNotFound`]
+    pub fn find_table_by_name_mut<'a>(&'a mut self, name: &str) -> Result<&'a mut ParamResCap> {
+        self.raw_find_table_by_name(name).map(|mut t| unsafe { t.as_mut() })
+    }
+
     /// Searches for a row by its id with a binary search, returning a pointer to its data.
     ///
     /// `id` must be a non-negative signed 32-bit integer.
@@ -97,6 +167,87 @@ impl ParamRepository {
         Ok(file.find_row(id)?)
     }
 
+    /// Searches for a row by its id like [`ParamRepository::get_row`],
+    /// returning a pointer to its data as the param struct `T`.
+    ///
+    /// # Errors:
+    /// - [`Error::MisalignedRow`] if the row data is not aligned for `T`.
+    /// - Same as [`ParamRepository::get_row`].
+    pub fn get_row_typed<T>(s: impl DLHash, id: i32) -> Result<NonNull<T>> {
+        let row = Self::get_row(s, id)?.cast::<T>();
+
+        if !row.as_ptr().is_aligned() {
+            return Err(Error::MisalignedRow);
+        }
+
+        Ok(row)
+    }
+
+    /// Searches for a row by its id like [`ParamRepository::get_row`],
+    /// returning a mutable reference to its data as the param struct `T`.
+    ///
+    /// # Safety
+    /// `T` must match the layout of the row data of the param table, and the row data
+    /// must not be accessed through any other reference while the returned one is alive.
+    ///
+    /// The returned reference is invalidated if the row is replaced or deleted.
+    ///
+    /// # Errors:
+    /// - Same as [`ParamRepository::get_row_typed`].
+    pub unsafe fn get_row_typed_mut<T>(s: impl DLHash, id: i32) -> Result<&'static mut T> {
+        Self::get_row_typed(s, id).map(|mut row| unsafe { row.as_mut() })
+    }
+
+    /// Locks the static [`ParamRepository`] for writing and returns a guard
+    /// that dereferences to the file of a param table.
+    ///
+    /// Other param manipulation functions block until the guard is dropped.
+    ///
+    /// # Errors:
+    /// - [`Error::NullInstance`] if static [`ParamRepository`] instance is null.
+    /// - [`Error::TableNotFound`]
+    pub fn file_locked<T: DLHash>(s: T) -> Result<ParamFileMut> {
+        let repo = PARAM_REPOSITORY.write().ok_or(Error::NullInstance)?;
+
+        let table = repo.raw_find_table(s)?;
+
+        Ok(ParamFileMut { _repo: repo, table })
+    }
+
+    /// The number of free row slots of a table that can be filled by
+    /// [`ParamRepository::insert_row`] without reallocating.
+    ///
+    /// # Errors:
+    /// - [`FileError::Malformed`] if the free list is invalid.
+    /// - [`Error::NullInstance`] if static [`ParamRepository`] instance is null.
+    /// - [`Error::TableNotFound`]
+    pub fn free_slot_count<T: DLHash>(s: T) -> Result<usize> {
+        let repo = PARAM_REPOSITORY.read().ok_or(Error::NullInstance)?;
+
+        let table = repo.find_table(s)?;
+        let file = table.file();
+
+        Ok(file.free_slot_count()?)
+    }
+
+    /// The id of the row that [`ParamRepository::insert_row`] would insert next.
+    ///
+    /// Never modifies the table, so the id is unknown if the table has no free row slots.
+    ///
+    /// # Errors:
+    /// - [`FileError::NeedsRealloc`] if the table has no free row slots.
+    /// - [`FileError::Malformed`] if the free list is invalid.
+    /// - [`Error::NullInstance`] if static [`ParamRepository`] instance is null.
+    /// - [`Error::TableNotFound`]
+    pub fn peek_next_free_id<T: DLHash>(s: T) -> Result<i32> {
+        let repo = PARAM_REPOSITORY.read().ok_or(Error::NullInstance)?;
+
+        let table = repo.find_table(s)?;
+        let file = table.file();
+
+        Ok(file.peek_next_free_id()?)
+    }
+
     /// Tries to insert a new row with fields pointed to by `data`
     /// and returns its positive id.
     ///
@@ -104,6 +255,7 @@ impl ParamRepository {
     ///
     /// # Errors:
     /// - [`FileError::FailedRealloc`] if necessary file reallocation failed.
+    /// - [`FileError::TooManyRows`] if the file has too many rows to be reallocated.
     /// - [`FileError::Malformed`] if popping from the free list returned an invalid entry.
     /// - [`Error::NullInstance`] if static [`ParamRepository`] instance is null.
     /// - [`Error::TableNotFound`]
@@ -134,6 +286,7 @@ impl ParamRepository {
     /// - [`FileError::NegativeId`] if `id` is negative.
     /// - [`FileError::NotInTable`] if the corresponding row is not found.
     /// - [`FileError::FailedRealloc`] if necessary file reallocation failed.
+    /// - [`FileError::TooManyRows`] if the file has too many rows to be reallocated.
     /// - [`FileError::Malformed`] if param file can't be parsed.
     /// - [`Error::NullInstance`] if static [`ParamRepository`] instance is null.
     /// - [`Error::TableNotFound`]
@@ -163,6 +316,7 @@ impl ParamRepository {
     /// # Errors:
     /// - [`FileError::NegativeId`] if `id` is negative.
     /// - [`FileError::FailedRealloc`] if necessary file reallocation failed.
+    /// - [`FileError::TooManyRows`] if the file has too many rows to be reallocated.
     /// - [`FileError::Malformed`] if pushing to the free list returned an invalid entry.
     /// - [`Error::NullInstance`] if static [`ParamRepository`] instance is null.
     /// - [`Error::TableNotFound`]
@@ -184,23 +338,184 @@ impl ParamRepository {
         Ok(new_file.delete_row(id)?)
     }
 
-    fn raw_find_table<'a, T: DLHash>(&'a self, s: T) -> Result<NonNull<ParamResCap>> {
-        unsafe {
-            let hash = s.strhash();
+    /// Writes a human-readable summary of every param table to `out`,
+    /// one table per line.
+    ///
+    /// Each line contains the name of the table, its row count, its file size in bytes,
+    /// the length of its lookup table and the number of its free row slots.
+    pub fn debug_dump(&self, out: &mut dyn fmt::Write) -> fmt::Result {
+        for next in self.res_rep.holder.iter() {
+            let name = next.name();
+
+            let file = next.file();
 
-            let mut bucket = self.res_rep.holder.bucket_for_hash(hash);
+            write!(out, "{name}: rows: ")?;
+            write_result(out, file.live_row_count())?;
 
-            while let Some(next) = bucket {
-                let next = next.as_ref();
-                bucket = next.res_cap.item.next;
+            write!(out, ", file size: {}, lut length: ", next.file_size)?;
+            write_result(out, file.row_count())?;
 
-                if next.res_cap.item.name.strhash() == hash {
-                    return Ok(next.into());
-                }
-            }
+            write!(out, ", free slots: ")?;
+            write_result(out, file.free_slot_count())?;
+
+            writeln!(out)?;
         }
 
-        Err(Error::TableNotFound)
+        Ok(())
+    }
+
+    /// Registers the param file `file` of `file_size` bytes as a new param table named `name`,
+    /// returning the table.
+    ///
+    /// The table is allocated with the allocator of the repository and copies the virtual
+    /// function tables of an existing table, so the game must have loaded at least one table.
+    /// It starts with a reference count of 1 held by pmod, so the engine does not destroy it.
+    ///
+    /// The table is recorded as owned by pmod, see [`is_owned_table`],
+    /// and can be removed with [`ParamRepository::unregister_table`].
+    ///
+    /// # Errors:
+    /// - [`Error::DuplicateTable`] if a table with the same `DLHash` exists.
+    /// - [`Error::EmptyRepository`] if the repository has no table to copy.
+    /// - [`Error::InvalidName`] if `name` could not be encoded.
+    /// - [`Error::FailedAlloc`] if the table could not be allocated.
+    /// - [`FileError::Malformed`] if `file_size` is smaller than the file header.
+    /// - [`Error::NullInstance`] if static [`ParamRepository`] instance is null.
+    ///
+    /// # Safety
+    /// `file` must point to a valid param file of `file_size` bytes, which must stay valid
+    /// and must not be accessed elsewhere while the table is registered.
+    pub unsafe fn register_table(
+        name: &str,
+        file: NonNull<FileHeader>,
+        file_size: usize,
+    ) -> Result<NonNull<ParamResCap>> {
+        if file_size < mem::size_of::<FileHeader>() {
+            return Err(FileError::Malformed.into());
+        }
+
+        let mut repo = PARAM_REPOSITORY.write().ok_or(Error::NullInstance)?;
+
+        let hash = name.strhash();
+
+        if repo.res_rep.find(PrehashedName(hash)).is_some() {
+            return Err(Error::DuplicateTable(hash));
+        }
+
+        let template = repo.res_rep.holder.iter().next().ok_or(Error::EmptyRepository)?;
+
+        let res_cap = ResCap::new_like(&template.res_cap, name, repo.alloc.clone())
+            .ok_or(Error::InvalidName)?;
+
+        let table = ParamResCap {
+            res_cap,
+            file_size,
+            file,
+        };
+
+        // Pin the table, since the engine may destroy unreferenced resources during cleanup
+        table.res_cap.item.acquire();
+
+        let layout = Layout::new::<ParamResCap>();
+        let ptr = unsafe { repo.alloc.alloc(layout).cast::<ParamResCap>() };
+        let ptr = NonNull::new(ptr).ok_or(Error::FailedAlloc)?;
+
+        unsafe { ptr.write(table) };
+
+        // The holder has buckets, since it holds the template
+        unsafe { repo.res_rep.holder.insert(ptr) };
+
+        let mut owned = OWNED_TABLES.lock().unwrap_or_else(PoisonError::into_inner);
+        owned.insert(ptr.as_ptr() as usize);
+
+        Ok(ptr)
+    }
+
+    /// Unlinks a param table from the static [`ParamRepository`], returning it.
+    ///
+    /// Tables not registered by pmod are only removed if `force` is `true`.
+    /// The returned table is not freed, and the game may still hold pointers to it.
+    ///
+    /// # Errors:
+    /// - [`Error::ForeignTable`] if the table was not registered by pmod and `force` is `false`.
+    /// - [`Error::NullInstance`] if static [`ParamRepository`] instance is null.
+    /// - [`Error::TableNotFound`]
+    pub fn unregister_table<T: DLHash>(s: T, force: bool) -> Result<NonNull<ParamResCap>> {
+        let mut repo = PARAM_REPOSITORY.write().ok_or(Error::NullInstance)?;
+
+        let hash = s.strhash();
+        let table = repo.raw_find_table(PrehashedName(hash))?;
+
+        if !force && !is_owned_table(table) {
+            return Err(Error::ForeignTable);
+        }
+
+        let table = repo
+            .res_rep
+            .holder
+            .remove_by_hash(hash)
+            .ok_or(Error::TableNotFound(hash))?;
+
+        let mut owned = OWNED_TABLES.lock().unwrap_or_else(PoisonError::into_inner);
+        owned.remove(&(table.as_ptr() as usize));
+
+        Ok(table)
+    }
+
+    /// Calls `f` with every param table while holding the read lock of the static
+    /// [`ParamRepository`] across all calls.
+    ///
+    /// `f` must not call param manipulation functions that lock the repository for writing.
+    ///
+    /// # Errors:
+    /// - [`Error::NullInstance`] if static [`ParamRepository`] instance is null.
+    pub fn with_all_tables<F: FnMut(&ParamResCap)>(f: F) -> Result<()> {
+        let repo = PARAM_REPOSITORY.read().ok_or(Error::NullInstance)?;
+
+        repo.res_rep.holder.iter().for_each(f);
+
+        Ok(())
+    }
+
+    /// Finds hashes shared by param tables with different names, returning each such hash
+    /// together with the sorted names of its tables.
+    ///
+    /// Tables with names that can't be decoded are ignored.
+    /// Returns an empty [`Vec`] if static [`ParamRepository`] instance is null.
+    pub fn audit_hash_collisions() -> Vec<(u32, Vec<String>)> {
+        PARAM_REPOSITORY
+            .read()
+            .map(|repo| repo.res_rep.holder.audit_hash_collisions())
+            .unwrap_or_default()
+    }
+
+    fn raw_find_table<'a, T: DLHash>(&'a self, s: T) -> Result<NonNull<ParamResCap>> {
+        let hash = s.strhash();
+
+        self.res_rep
+            .find(PrehashedName(hash))
+            .ok_or(Error::TableNotFound(hash))
+    }
+
+    fn raw_find_table_by_name(&self, name: &str) -> Result<NonNull<ParamResCap>> {
+        self.res_rep
+            .find_by_str(name)
+            .ok_or_else(|| Error::TableNotFound(name.strhash()))
+    }
+}
+
+/// Returns whether a param table was registered by pmod, see [`ParamRepository::register_table`].
+///
+/// The registry is local to this copy of pmod.
+pub fn is_owned_table(table: NonNull<ParamResCap>) -> bool {
+    let owned = OWNED_TABLES.lock().unwrap_or_else(PoisonError::into_inner);
+    owned.contains(&(table.as_ptr() as usize))
+}
+
+fn write_result(out: &mut dyn fmt::Write, result: file::Result<usize>) -> fmt::Result {
+    match result {
+        Ok(value) => write!(out, "{value}"),
+        Err(e) => write!(out, "{e}"),
     }
 }
 
@@ -215,10 +530,53 @@ impl ParamResCap {
         unsafe { self.file.as_mut() }
     }
 
+    /// Get the name of the param table.
+    ///
+    /// If the name can't be decoded, it is looked up by its hash in [`HashRegistry`].
+    /// Returns `"ERROR"` if it is not registered either.
+    pub fn name(&self) -> Cow<'_, str> {
+        let name = &self.res_cap.item.name;
+
+        name.read()
+            .or_else(|| HashRegistry::lookup(name.strhash()).map(Cow::Borrowed))
+            .unwrap_or(Cow::Borrowed("ERROR"))
+    }
+
     /// Get the size of file in bytes.
     pub fn file_size(&self) -> usize {
         self.file_size
     }
+
+    /// Get the raw bytes of the held file.
+    ///
+    /// `&[u8]` implements [`std::io::Read`], so the file can be streamed
+    /// into any writer with [`std::io::copy`].
+    pub fn file_bytes(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(self.file.as_ptr() as *const u8, self.file_size) }
+    }
+}
+
+impl ParamFileMut {
+    /// Get the param table the file belongs to.
+    pub fn table(&self) -> &ParamResCap {
+        // SAFETY: the table is owned by the repository locked by `self._repo`
+        unsafe { self.table.as_ref() }
+    }
+}
+
+impl Deref for ParamFileMut {
+    type Target = FileHeader;
+
+    fn deref(&self) -> &FileHeader {
+        self.table().file()
+    }
+}
+
+impl DerefMut for ParamFileMut {
+    fn deref_mut(&mut self) -> &mut FileHeader {
+        // SAFETY: the table is owned by the repository locked by `self._repo`
+        unsafe { self.table.as_mut().file_mut() }
+    }
 }
 
 impl AsRef<ResCapHolderItem<ParamResCap>> for ParamResCap {
@@ -227,14 +585,27 @@ impl AsRef<ResCapHolderItem<ParamResCap>> for ParamResCap {
     }
 }
 
+impl AsMut<ResCapHolderItem<ParamResCap>> for ParamResCap {
+    fn as_mut(&mut self) -> &mut ResCapHolderItem<ParamResCap> {
+        self.res_cap.as_mut()
+    }
+}
+
+impl AsRef<FileHeader> for ParamResCap {
+    fn as_ref(&self) -> &FileHeader {
+        self.file()
+    }
+}
+
+impl AsMut<FileHeader> for ParamResCap {
+    fn as_mut(&mut self) -> &mut FileHeader {
+        self.file_mut()
+    }
+}
+
 impl fmt::Debug for ParamResCap {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let name = self
-            .res_cap
-            .item
-            .name
-            .read()
-            .unwrap_or(Cow::Borrowed("ERROR"));
+        let name = self.name();
 
         f.debug_struct("ParamResCap")
             .field("name", &name.as_ref())
@@ -245,22 +616,9 @@ impl fmt::Debug for ParamResCap {
 
 impl fmt::Debug for ParamRepository {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut dbg = f.debug_list();
-
-        unsafe {
-            let buckets = self.res_rep.holder.as_slice();
-
-            for mut bucket in buckets.iter().copied() {
-                while let Some(next) = bucket {
-                    let next = next.as_ref();
-                    bucket = next.res_cap.item.next;
-
-                    dbg.entry(next);
-                }
-            }
-        }
-
-        dbg.finish_non_exhaustive()
+        f.debug_list()
+            .entries(self.res_rep.holder.iter())
+            .finish_non_exhaustive()
     }
 }
 
@@ -276,7 +634,17 @@ impl StaticPtr for ParamRepository {
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        fmt::Debug::fmt(&self, f)
+        match self {
+            Self::TableNotFound(hash) => match HashRegistry::lookup(*hash) {
+                Some(name) => write!(f, "TableNotFound({name:?})"),
+                None => write!(f, "TableNotFound({})", DLHashDisplay(*hash)),
+            },
+            Self::DuplicateTable(hash) => match HashRegistry::lookup(*hash) {
+                Some(name) => write!(f, "DuplicateTable({name:?})"),
+                None => write!(f, "DuplicateTable({})", DLHashDisplay(*hash)),
+            },
+            _ => fmt::Debug::fmt(&self, f),
+        }
     }
 }
 
@@ -2,9 +2,16 @@
 //!
 //! Credits to vswarte and eldenring-rs for some of the layouts
 
-use std::{ptr::NonNull, slice};
+use std::{
+    alloc::{GlobalAlloc, Layout},
+    collections::{BTreeMap, BTreeSet},
+    error, fmt, mem,
+    ptr::NonNull,
+    slice,
+    sync::atomic::{AtomicU32, Ordering},
+};
 
-use crate::{stdalloc::DLStdAllocator, string::DLHashString};
+use crate::{hash::DLHash, stdalloc::DLStdAllocator, string::DLHashString};
 
 #[repr(C)]
 pub struct ResCap<T>
@@ -53,27 +60,445 @@ where
     pub name: DLHashString,
     pub owner: Option<NonNull<ResCapHolder<T>>>,
     pub next: Option<NonNull<T>>,
-    pub refcount: u32,
+    pub refcount: AtomicU32,
+}
+
+/// Chain length statistics of a [`ResCapHolder`], see [`ResCapHolder::items_per_bucket_stats`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BucketStats {
+    /// The number of buckets.
+    pub bucket_count: usize,
+
+    /// The number of items in all buckets.
+    pub item_count: usize,
+
+    /// The number of buckets without any items.
+    pub empty_buckets: usize,
+
+    /// The number of items in the longest bucket chain.
+    pub longest_chain: usize,
+}
+
+/// Possible [`ResCapHolder::rehash`] errors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RehashError {
+    /// The new number of buckets is 0.
+    ZeroLength,
+
+    /// Could not allocate the new bucket array.
+    AllocationFailed,
 }
 
 impl<T> ResCapHolder<T>
 where
     T: AsRef<ResCapHolderItem<T>>,
 {
+    /// Returns the first item in the bucket chain of `hash`,
+    /// or [`None`] if the holder has no buckets.
+    ///
+    /// # Safety
+    /// `buckets` must point to `len` readable buckets.
     pub unsafe fn bucket_for_hash(&self, hash: u32) -> Option<NonNull<T>> {
-        let index = hash % self.len;
+        let index = hash.checked_rem(self.len)?;
         unsafe { self.buckets.add(index as usize).read() }
     }
 
+    /// Returns the first item in the bucket chain of `hash`,
+    /// or [`None`] if the bucket is empty or the holder has no buckets.
+    pub fn bucket(&self, hash: u32) -> Option<&T> {
+        unsafe { self.bucket_for_hash(hash).map(|next| next.as_ref()) }
+    }
+
+    /// Returns the heads of all bucket chains.
+    pub fn buckets(&self) -> &[Option<NonNull<T>>] {
+        unsafe { self.as_slice() }
+    }
+
+    /// Finds an item by its `DLHash` by walking the corresponding bucket chain.
+    pub fn find_by_name<H: DLHash>(&self, name: H) -> Option<NonNull<T>> {
+        let hash = name.strhash();
+
+        unsafe { ChainIter::new(self.bucket_for_hash(hash)) }
+            .find(|next| unsafe { next.as_ref().as_ref().name.strhash() == hash })
+    }
+
+    /// Finds an item by its name by walking the bucket chain of its `DLHash`,
+    /// comparing names with [`DLHashString::eq_dlcase`] to rule out hash collisions.
+    pub fn find_by_str(&self, name: &str) -> Option<NonNull<T>> {
+        let hash = name.strhash();
+
+        unsafe { ChainIter::new(self.bucket_for_hash(hash)) }.find(|next| {
+            let item = unsafe { next.as_ref().as_ref() };
+            item.name.strhash() == hash && item.name.eq_dlcase(name)
+        })
+    }
+
+    /// Iterates over every item in the holder, bucket by bucket.
+    ///
+    /// Each bucket chain is walked with cycle detection, so a corrupted `next` pointer
+    /// ends the chain instead of looping forever.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.iter_ptrs().map(|next| unsafe { next.as_ref() })
+    }
+
+    /// Iterates over pointers to every item in the holder, bucket by bucket.
+    ///
+    /// Unlike [`ResCapHolder::iter`], the pointers may be used to mutate the items
+    /// while the lock owning the holder is held for writing.
+    pub fn iter_ptrs(&self) -> impl Iterator<Item = NonNull<T>> + '_ {
+        self.buckets()
+            .iter()
+            .flat_map(|bucket| unsafe { ChainIter::new(*bucket) })
+    }
+
+    /// Unlinks the item with the `DLHash` `hash` from its bucket chain, returning it.
+    ///
+    /// The `owner` and `next` of the removed item are cleared, and the caller decides
+    /// what to do with its memory. Returns [`None`] without touching memory if no item
+    /// has the hash.
+    pub fn remove_by_hash(&mut self, hash: u32) -> Option<NonNull<T>>
+    where
+        T: AsMut<ResCapHolderItem<T>>,
+    {
+        if self.len == 0 {
+            return None;
+        }
+
+        let index = (hash % self.len) as usize;
+        let mut prev = None::<NonNull<T>>;
+
+        for mut next in unsafe { ChainIter::new(self.bucket_for_hash(hash)) } {
+            let item = unsafe { next.as_mut().as_mut() };
+
+            if item.name.strhash() != hash {
+                prev = Some(next);
+                continue;
+            }
+
+            let after = item.next.take();
+            item.owner = None;
+
+            match prev {
+                Some(mut prev) => unsafe { prev.as_mut().as_mut().next = after },
+                None => unsafe { self.as_mut_slice()[index] = after },
+            }
+
+            return Some(next);
+        }
+
+        None
+    }
+
+    /// Links `item` at the head of the bucket chain for its `DLHash`,
+    /// setting its owner to the holder.
+    ///
+    /// Returns `false` without linking the item if the holder has no buckets.
+    ///
+    /// # Safety
+    /// `item` must be valid for reads and writes for as long as it is linked,
+    /// and must not already be linked in a holder.
+    pub unsafe fn insert(&mut self, mut item: NonNull<T>) -> bool
+    where
+        T: AsMut<ResCapHolderItem<T>>,
+    {
+        let hash = unsafe { item.as_ref().as_ref().name.strhash() };
+
+        let Some(index) = hash.checked_rem(self.len) else {
+            return false;
+        };
+
+        let owner = NonNull::from(&mut *self);
+        let bucket = unsafe { &mut self.as_mut_slice()[index as usize] };
+
+        let entry = unsafe { item.as_mut().as_mut() };
+        entry.owner = Some(owner);
+        entry.next = bucket.replace(item);
+
+        true
+    }
+
+    /// Redistributes every item into `new_len` buckets allocated with the holder's allocator,
+    /// freeing the old bucket array.
+    ///
+    /// Items with the same bucket keep their relative order. Long chains make lookups slow,
+    /// see [`ResCapHolder::items_per_bucket_stats`].
+    ///
+    /// # Errors:
+    /// - [`RehashError::ZeroLength`] if `new_len` is 0.
+    /// - [`RehashError::AllocationFailed`] if the new bucket array could not be allocated,
+    ///   in which case the holder is left untouched.
+    pub fn rehash(&mut self, new_len: u32) -> Result<(), RehashError>
+    where
+        T: AsMut<ResCapHolderItem<T>>,
+    {
+        if new_len == 0 {
+            return Err(RehashError::ZeroLength);
+        }
+
+        // Items of corrupted chains may be yielded more than once
+        let mut seen = BTreeSet::new();
+        let items = self
+            .iter_ptrs()
+            .filter(|&item| seen.insert(item))
+            .collect::<Vec<_>>();
+
+        let layout = Layout::array::<Option<NonNull<T>>>(new_len as usize)
+            .map_err(|_| RehashError::AllocationFailed)?;
+
+        let new_buckets = unsafe { self.alloc.alloc_zeroed(layout) } as *mut Option<NonNull<T>>;
+        let new_buckets = NonNull::new(new_buckets).ok_or(RehashError::AllocationFailed)?;
+
+        let buckets = unsafe { slice::from_raw_parts_mut(new_buckets.as_ptr(), new_len as usize) };
+
+        // Linked in reverse, so chains keep the relative order of their items
+        for mut next in items.into_iter().rev() {
+            let item = unsafe { next.as_mut().as_mut() };
+            let bucket = &mut buckets[(item.name.strhash() % new_len) as usize];
+
+            item.next = bucket.replace(next);
+        }
+
+        let old_len = mem::replace(&mut self.len, new_len);
+        let old_buckets = mem::replace(&mut self.buckets, new_buckets);
+
+        if old_len != 0 {
+            unsafe {
+                let old_layout =
+                    Layout::array::<Option<NonNull<T>>>(old_len as usize).unwrap_unchecked();
+                self.alloc.dealloc(old_buckets.as_ptr() as _, old_layout);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the number of buckets and items, and how the items are distributed.
+    pub fn items_per_bucket_stats(&self) -> BucketStats {
+        let mut stats = BucketStats {
+            bucket_count: self.len as usize,
+            item_count: 0,
+            empty_buckets: 0,
+            longest_chain: 0,
+        };
+
+        for bucket in self.buckets() {
+            let len = unsafe { ChainIter::new(*bucket) }.count();
+
+            stats.item_count += len;
+            stats.empty_buckets += (len == 0) as usize;
+            stats.longest_chain = Ord::max(stats.longest_chain, len);
+        }
+
+        stats
+    }
+
+    /// Finds hashes shared by items with different names, returning each such hash
+    /// together with the sorted names of its items.
+    ///
+    /// Items with names that can't be decoded are ignored.
+    pub fn audit_hash_collisions(&self) -> Vec<(u32, Vec<String>)> {
+        let mut names_by_hash = BTreeMap::<u32, BTreeSet<String>>::new();
+
+        for item in self.iter() {
+            let item = item.as_ref();
+
+            if let Some(name) = item.name.read() {
+                names_by_hash
+                    .entry(item.name.strhash())
+                    .or_default()
+                    .insert(name.into_owned());
+            }
+        }
+
+        names_by_hash
+            .into_iter()
+            .filter(|(_, names)| names.len() > 1)
+            .map(|(hash, names)| (hash, names.into_iter().collect()))
+            .collect()
+    }
+
+    /// Counts buckets by the length of their chain, where index `i` is the number
+    /// of buckets with a chain length of `i`, and the last index counts longer chains too.
+    ///
+    /// Useful for diagnosing the distribution of `DLHash` values.
+    pub fn debug_histogram(&self) -> [usize; 8] {
+        let mut histogram = [0; 8];
+
+        for bucket in self.buckets() {
+            let len = unsafe { ChainIter::new(*bucket) }.count();
+            histogram[Ord::min(len, histogram.len() - 1)] += 1;
+        }
+
+        histogram
+    }
+
+    /// # Safety
+    /// `buckets` must point to `len` readable buckets.
     pub unsafe fn as_slice(&self) -> &[Option<NonNull<T>>] {
         unsafe { slice::from_raw_parts(self.buckets.as_ptr(), self.len as usize) }
     }
 
+    /// # Safety
+    /// `buckets` must point to `len` writable buckets.
     pub unsafe fn as_mut_slice(&mut self) -> &mut [Option<NonNull<T>>] {
         unsafe { slice::from_raw_parts_mut(self.buckets.as_ptr(), self.len as usize) }
     }
 }
 
+impl<T> ResCap<T>
+where
+    T: AsRef<ResCapHolderItem<T>>,
+{
+    /// Creates an unlinked resource capsule named `name`, see [`ResCapHolderItem::new_like`].
+    ///
+    /// Game specific flags are copied from `template`, and the capsule has no debug item.
+    pub fn new_like(template: &Self, name: &str, alloc: DLStdAllocator) -> Option<Self> {
+        Some(Self {
+            item: ResCapHolderItem::new_like(&template.item, name, alloc)?,
+            #[cfg(feature = "elden-ring")]
+            is_debug: template.is_debug,
+            #[cfg(any(feature = "elden-ring", feature = "sekiro"))]
+            _unk61: template._unk61,
+            #[cfg(feature = "elden-ring")]
+            debug_item: 0,
+            #[cfg(feature = "elden-ring")]
+            _unk70: template._unk70,
+        })
+    }
+}
+
+impl<T> ResRep<T>
+where
+    T: AsRef<ResCapHolderItem<T>>,
+{
+    /// Finds a resource by its `DLHash` in the holder of the repository,
+    /// see [`ResCapHolder::find_by_name`].
+    pub fn find<H: DLHash>(&self, name: H) -> Option<NonNull<T>> {
+        self.holder.find_by_name(name)
+    }
+
+    /// Finds a resource by its name in the holder of the repository,
+    /// see [`ResCapHolder::find_by_str`].
+    pub fn find_by_str(&self, name: &str) -> Option<NonNull<T>> {
+        self.holder.find_by_str(name)
+    }
+}
+
+impl<T> ResCapHolderItem<T>
+where
+    T: AsRef<ResCapHolderItem<T>>,
+{
+    /// Creates an unlinked item named `name` with a reference count of 0,
+    /// copying the virtual function tables and the name encoding of `template`.
+    ///
+    /// The name is allocated with `alloc`. Returns [`None`] if `name` could not be encoded.
+    pub fn new_like(template: &Self, name: &str, alloc: DLStdAllocator) -> Option<Self> {
+        let tag = template.name.encoding();
+        let vtable = template.name.vtable();
+
+        Some(Self {
+            _vtable: template._vtable,
+            name: DLHashString::from_str_in(name, tag, alloc, vtable)?,
+            owner: None,
+            next: None,
+            refcount: AtomicU32::new(0),
+        })
+    }
+
+    /// Returns the reference count of the item, for diagnostics.
+    ///
+    /// The engine considers an item owned while its count is non-zero,
+    /// and may destroy items with a count of 0 during cleanup.
+    pub fn refcount(&self) -> u32 {
+        self.refcount.load(Ordering::Acquire)
+    }
+
+    /// Atomically increments the reference count of the item.
+    pub fn acquire(&self) {
+        self.refcount.fetch_add(1, Ordering::AcqRel);
+    }
+
+    /// Atomically decrements the reference count of the item, saturating at 0.
+    pub fn release(&self) {
+        let _ = self
+            .refcount
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |c| c.checked_sub(1));
+    }
+}
+
+impl BucketStats {
+    /// The average number of items per bucket.
+    pub fn load_factor(&self) -> f64 {
+        self.item_count as f64 / self.bucket_count.max(1) as f64
+    }
+}
+
+impl fmt::Display for RehashError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self, f)
+    }
+}
+
+impl error::Error for RehashError {}
+
+/// Walks a bucket chain, stopping at the first revisited item.
+///
+/// Revisits are detected with Brent's algorithm, so an item in a cycle
+/// may be yielded more than once before the walk stops.
+struct ChainIter<T>
+where
+    T: AsRef<ResCapHolderItem<T>>,
+{
+    next: Option<NonNull<T>>,
+    saved: Option<NonNull<T>>,
+    steps: usize,
+    power: usize,
+}
+
+impl<T> ChainIter<T>
+where
+    T: AsRef<ResCapHolderItem<T>>,
+{
+    /// # Safety
+    ///
+    /// Every item in the chain starting at `head` must be valid for reads.
+    unsafe fn new(head: Option<NonNull<T>>) -> Self {
+        Self {
+            next: head,
+            saved: None,
+            steps: 0,
+            power: 1,
+        }
+    }
+}
+
+impl<T> Iterator for ChainIter<T>
+where
+    T: AsRef<ResCapHolderItem<T>>,
+{
+    type Item = NonNull<T>;
+
+    fn next(&mut self) -> Option<NonNull<T>> {
+        let current = self.next.take()?;
+
+        if self.saved == Some(current) {
+            return None;
+        }
+
+        self.steps += 1;
+
+        if self.steps == self.power {
+            self.saved = Some(current);
+            self.power *= 2;
+            self.steps = 0;
+        }
+
+        self.next = unsafe { current.as_ref().as_ref().next };
+
+        Some(current)
+    }
+}
+
 impl<T> AsRef<ResCapHolderItem<T>> for ResCap<T>
 where
     T: AsRef<ResCapHolderItem<T>>,
@@ -83,6 +508,15 @@ where
     }
 }
 
+impl<T> AsMut<ResCapHolderItem<T>> for ResCap<T>
+where
+    T: AsRef<ResCapHolderItem<T>>,
+{
+    fn as_mut(&mut self) -> &mut ResCapHolderItem<T> {
+        &mut self.item
+    }
+}
+
 impl<T> AsRef<ResCapHolderItem<T>> for ResRep<T>
 where
     T: AsRef<ResCapHolderItem<T>>,
@@ -92,6 +526,15 @@ where
     }
 }
 
+impl<T> AsMut<ResCapHolderItem<T>> for ResRep<T>
+where
+    T: AsRef<ResCapHolderItem<T>>,
+{
+    fn as_mut(&mut self) -> &mut ResCapHolderItem<T> {
+        &mut self.resource.item
+    }
+}
+
 unsafe impl<T: AsRef<ResCapHolderItem<T>>> Send for ResCap<T> {}
 
 unsafe impl<T: AsRef<ResCapHolderItem<T>>> Sync for ResCap<T> {}
@@ -99,3 +542,153 @@ unsafe impl<T: AsRef<ResCapHolderItem<T>>> Sync for ResCap<T> {}
 unsafe impl<T: AsRef<ResCapHolderItem<T>>> Send for ResRep<T> {}
 
 unsafe impl<T: AsRef<ResCapHolderItem<T>>> Sync for ResRep<T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::string::DLStringEncoding;
+
+    struct TestRes {
+        item: ResCapHolderItem<TestRes>,
+    }
+
+    impl AsRef<ResCapHolderItem<Self>> for TestRes {
+        fn as_ref(&self) -> &ResCapHolderItem<Self> {
+            &self.item
+        }
+    }
+
+    impl AsMut<ResCapHolderItem<Self>> for TestRes {
+        fn as_mut(&mut self) -> &mut ResCapHolderItem<Self> {
+            &mut self.item
+        }
+    }
+
+    /// A holder with `len` buckets, which must be freed with [`free_buckets`].
+    fn test_holder(len: u32) -> ResCapHolder<TestRes> {
+        let mut holder = ResCapHolder {
+            _vtable: 0,
+            alloc: DLStdAllocator::default(),
+            owner: None,
+            _unk18: 0,
+            len: 0,
+            buckets: NonNull::dangling(),
+        };
+
+        holder.rehash(len).unwrap();
+        holder
+    }
+
+    fn free_buckets(holder: &mut ResCapHolder<TestRes>) {
+        let layout = Layout::array::<Option<NonNull<TestRes>>>(holder.len as usize).unwrap();
+        unsafe { holder.alloc.dealloc(holder.buckets.as_ptr() as _, layout) };
+    }
+
+    fn insert_all(holder: &mut ResCapHolder<TestRes>, names: &[&str]) -> Vec<TestRes> {
+        let mut items: Vec<_> = names
+            .iter()
+            .map(|name| TestRes {
+                item: ResCapHolderItem {
+                    _vtable: 0,
+                    name: DLHashString::from_str_in(
+                        name,
+                        DLStringEncoding::UTF16,
+                        DLStdAllocator::default(),
+                        0,
+                    )
+                    .unwrap(),
+                    owner: None,
+                    next: None,
+                    refcount: AtomicU32::new(0),
+                },
+            })
+            .collect();
+
+        // The items are linked by address, so the vector is never grown after this.
+        for item in &mut items {
+            assert!(unsafe { holder.insert(NonNull::from(item)) });
+        }
+
+        items
+    }
+
+    fn names(holder: &ResCapHolder<TestRes>) -> Vec<String> {
+        holder
+            .iter()
+            .map(|item| item.item.name.read().unwrap().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn rehash_redistributes_items() {
+        let names = (0..20).map(|i| format!("Param{i}")).collect::<Vec<_>>();
+        let names = names.iter().map(String::as_str).collect::<Vec<_>>();
+
+        let mut holder = test_holder(1);
+        let _items = insert_all(&mut holder, &names);
+
+        let stats = holder.items_per_bucket_stats();
+
+        assert_eq!(
+            stats,
+            BucketStats {
+                bucket_count: 1,
+                item_count: 20,
+                empty_buckets: 0,
+                longest_chain: 20,
+            }
+        );
+
+        holder.rehash(17).unwrap();
+
+        let stats = holder.items_per_bucket_stats();
+
+        assert_eq!(stats.bucket_count, 17);
+        assert_eq!(stats.item_count, 20);
+        assert!(stats.longest_chain < 20);
+        assert_eq!(stats.load_factor(), 20.0 / 17.0);
+
+        for name in names {
+            let item = holder.find_by_str(name).expect(name);
+            let head = unsafe { holder.bucket_for_hash(name.strhash()) };
+
+            // The item is linked in the chain of its new bucket
+            assert!(
+                unsafe { ChainIter::new(head) }.any(|next| next == item),
+                "{name}"
+            );
+        }
+
+        free_buckets(&mut holder);
+    }
+
+    #[test]
+    fn rehash_keeps_chain_order() {
+        let mut holder = test_holder(1);
+        let _items = insert_all(&mut holder, &["a", "b", "c"]);
+
+        // Items are linked at the head of their chain
+        assert_eq!(names(&holder), ["c", "b", "a"]);
+
+        holder.rehash(1).unwrap();
+
+        assert_eq!(names(&holder), ["c", "b", "a"]);
+
+        free_buckets(&mut holder);
+    }
+
+    #[test]
+    fn rehash_to_zero_buckets_fails() {
+        let mut holder = test_holder(4);
+        let _items = insert_all(&mut holder, &["a", "b"]);
+
+        let buckets = holder.buckets;
+
+        assert_eq!(holder.rehash(0), Err(RehashError::ZeroLength));
+        assert_eq!(holder.len, 4);
+        assert_eq!(holder.buckets, buckets);
+        assert_eq!(holder.items_per_bucket_stats().item_count, 2);
+
+        free_buckets(&mut holder);
+    }
+}
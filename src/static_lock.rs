@@ -20,6 +20,11 @@ use windows::{
     },
 };
 
+/// A reader-writer lock guarding a game singleton of type `T`.
+///
+/// The underlying `SRWLOCK` lives in a named file mapping identified by
+/// [`StaticPtr::STATIC_ID`], so every module in the process that uses pmod
+/// synchronizes on the same lock.
 pub struct StaticLock<T: StaticPtr> {
     lock: NonNull<SRWLOCK>,
     _marker: PhantomData<T>,
@@ -37,34 +42,19 @@ pub struct StaticLockWriteGuard<'a, T> {
 
 impl<T: StaticPtr + FromSingleton> StaticLock<T> {
     pub fn new() -> Self {
-        const RWLOCK_SIZE: usize = mem::size_of::<SRWLOCK>();
-
-        unsafe {
+        Self {
             // Starts zero-initialized, valid for SRWLOCK.
-            let mapping_handle = CreateFileMappingW(
-                INVALID_HANDLE_VALUE,
-                None,
-                PAGE_READWRITE,
-                0,
-                RWLOCK_SIZE as u32,
-                T::STATIC_ID,
-            )
-            .expect("CreateFileMappingW failed");
-
-            let mapping =
-                MapViewOfFile(mapping_handle, FILE_MAP_ALL_ACCESS, 0, 0, RWLOCK_SIZE).Value;
-
-            let Some(lock) = NonNull::new(mapping as _) else {
-                panic!("MapViewOfFile failed: {}", GetLastError().ok().unwrap_err());
-            };
-
-            Self {
-                lock,
-                _marker: PhantomData,
-            }
+            lock: unsafe { map_shared(T::STATIC_ID) },
+            _marker: PhantomData,
         }
     }
 
+    /// Returns whether the address of the singleton can be resolved,
+    /// without acquiring the lock.
+    pub fn is_initialized(&self) -> bool {
+        from_singleton::address_of::<T>().is_some()
+    }
+
     pub fn read(&self) -> Option<StaticLockReadGuard<'_, T>> {
         Some(StaticLockReadGuard::new(
             self.lock,
@@ -80,6 +70,38 @@ impl<T: StaticPtr + FromSingleton> StaticLock<T> {
     }
 }
 
+/// Maps a named, zero-initialized `T` shared by every module in the process.
+///
+/// The mapping is never unmapped, so the returned pointer stays valid for the lifetime
+/// of the process.
+///
+/// # Safety
+/// `T` must be valid when zero-initialized, and every mapping of `name` must be of the same `T`.
+///
+/// # Panics
+/// If the file mapping could not be created or mapped.
+pub(crate) unsafe fn map_shared<T>(name: PCWSTR) -> NonNull<T> {
+    let size = mem::size_of::<T>();
+
+    let mapping_handle = CreateFileMappingW(
+        INVALID_HANDLE_VALUE,
+        None,
+        PAGE_READWRITE,
+        0,
+        size as u32,
+        name,
+    )
+    .expect("CreateFileMappingW failed");
+
+    let mapping = MapViewOfFile(mapping_handle, FILE_MAP_ALL_ACCESS, 0, 0, size).Value;
+
+    let Some(ptr) = NonNull::new(mapping as _) else {
+        panic!("MapViewOfFile failed: {}", GetLastError().ok().unwrap_err());
+    };
+
+    ptr
+}
+
 impl<T> StaticLockReadGuard<'_, T> {
     fn new(lock: NonNull<SRWLOCK>, ptr: NonNull<T>) -> Self {
         unsafe {
@@ -148,14 +170,25 @@ impl<T> Drop for StaticLockWriteGuard<'_, T> {
     }
 }
 
+// SAFETY: `lock` points into a named file mapping that is never unmapped, so it stays
+// valid for the lifetime of the process regardless of which thread created it.
+// The guarded `T` is a process-wide singleton, and moving the handle to another thread
+// only moves the right to lock it, which requires `T: Send` as with `std::sync::RwLock`.
 unsafe impl<T: Send + StaticPtr> Send for StaticLock<T> {}
 
+// SAFETY: SRW locks may be acquired and released from any thread. Shared access hands out
+// `&T` to several threads at once (`T: Sync`) and exclusive access hands out `&mut T`
+// to whichever thread acquired it (`T: Send`), matching `std::sync::RwLock`.
 unsafe impl<T: Send + Sync + StaticPtr> Sync for StaticLock<T> {}
 
-unsafe impl<T: Send> Send for StaticLockReadGuard<'_, T> {}
+// The guards are intentionally not `Send`: SRW locks must be released by the thread
+// that acquired them, so a guard must be dropped on the thread that created it,
+// as with the guards of `std::sync::RwLock`.
 
+// SAFETY: a shared reference to a guard only hands out `&T`, which may be used
+// from several threads at once if `T: Sync`. The lock itself is not touched.
 unsafe impl<T: Send + Sync> Sync for StaticLockReadGuard<'_, T> {}
 
-unsafe impl<T: Send> Send for StaticLockWriteGuard<'_, T> {}
-
+// SAFETY: a shared reference to a guard only hands out `&T`, since `DerefMut`
+// requires `&mut` access to the guard, so `T: Sync` is sufficient as above.
 unsafe impl<T: Send + Sync> Sync for StaticLockWriteGuard<'_, T> {}
@@ -1,7 +1,8 @@
 //! Monomorphized DLBasicString template using the provided encoding discriminator
 //! used by the Dantelion2 FromSoftware in-house library.
 //!
-//! [`DLString`] instances can be read and written from existing structures, but not created.
+//! [`DLString`] instances can be read and written from existing structures,
+//! or created with [`DLString::from_str_in`].
 //!
 //! [`DLHashString`] instances cache its DLHash 32-bit hash using interior mutability.
 //!
@@ -9,20 +10,35 @@
 
 use std::{
     borrow::Cow,
-    ffi::OsString,
+    error,
+    ffi::{OsStr, OsString},
     fmt,
-    mem::ManuallyDrop,
-    os::windows::ffi::OsStringExt,
+    hash::{Hash, Hasher},
+    mem::{self, ManuallyDrop},
+    os::windows::ffi::{OsStrExt, OsStringExt},
+    slice,
+    str,
     sync::atomic::{AtomicBool, AtomicU32, Ordering},
 };
 
 use cxx_stl::string::{CxxNarrowString, CxxUtf8String, CxxUtf16String, CxxUtf32String};
+use encoding_rs::{DecoderResult, EncoderResult};
 
-use crate::{hash::DLHash, stdalloc::DLStdAllocator};
+use crate::{
+    hash::{eq_dlcase_units, DLHash, DLHash64},
+    stdalloc::DLStdAllocator,
+};
+
+#[cfg(feature = "serde")]
+mod serialize;
+
+#[cfg(feature = "serde")]
+pub use serialize::OwnedDLStringData;
 
 /// Monomorphized `DLTX::DLBasicString` template using the provided encoding discriminator.
 /// 
-/// It can be read and written from existing structures, but not created.
+/// It can be read and written from existing structures, or created with
+/// [`DLString::new_in`] and [`DLString::from_str_in`].
 #[repr(C)]
 pub struct DLString {
     union: DLStringUnion,
@@ -40,10 +56,11 @@ pub struct DLHashString {
     hash: DLStringHash,
 }
 
+/// Encoding discriminator of a [`DLString`].
 #[repr(u8)]
-#[allow(non_camel_case_types, dead_code)]
-#[derive(Clone, Copy, Debug)]
-enum DLStringTag {
+#[allow(non_camel_case_types)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DLStringEncoding {
     UTF8 = 0,
     UTF16 = 1,
     ISO_8859 = 2,
@@ -52,6 +69,50 @@ enum DLStringTag {
     UTF32 = 5,
 }
 
+type DLStringTag = DLStringEncoding;
+
+/// The stored code units of a [`DLString`], see [`DLString::as_raw`].
+#[allow(non_camel_case_types)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RawStringView<'a> {
+    UTF8(&'a [u8]),
+    UTF16(&'a [u16]),
+    ISO_8859(&'a [u8]),
+    SJIS(&'a [u8]),
+    EUC_JP(&'a [u8]),
+    UTF32(&'a [u32]),
+}
+
+/// A string could not be decoded by [`DLString::try_read`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReadError {
+    /// The index of the first invalid code unit.
+    pub unit_index: usize,
+
+    /// Why the code unit is invalid.
+    pub kind: ReadErrorKind,
+}
+
+/// The kind of a [`ReadError`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReadErrorKind {
+    /// The code unit is part of a sequence that is invalid for the encoding.
+    Malformed,
+
+    /// The code unit is an unpaired UTF-16 surrogate or a UTF-32 surrogate value.
+    Surrogate,
+
+    /// The UTF-32 code unit is greater than [`char::MAX`].
+    OutOfRange,
+}
+
+/// A string could not be encoded by [`DLString::try_write`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WriteError {
+    /// The index of the first character that could not be encoded.
+    pub char_index: usize,
+}
+
 #[repr(C)]
 union DLStringUnion {
     utf8: ManuallyDrop<CxxUtf8String<DLStdAllocator>>,
@@ -70,6 +131,174 @@ struct DLStringHash {
 }
 
 impl DLString {
+    /// Creates a new empty string with the encoding `tag` in the allocator `alloc`.
+    pub fn new_in(tag: DLStringEncoding, alloc: DLStdAllocator) -> Self {
+        let union = match tag {
+            DLStringTag::UTF8 => DLStringUnion {
+                utf8: ManuallyDrop::new(CxxUtf8String::new_in(alloc)),
+            },
+            DLStringTag::UTF16 => DLStringUnion {
+                utf16: ManuallyDrop::new(CxxUtf16String::new_in(alloc)),
+            },
+            DLStringTag::ISO_8859 => DLStringUnion {
+                iso_8859: ManuallyDrop::new(CxxNarrowString::new_in(alloc)),
+            },
+            DLStringTag::SJIS => DLStringUnion {
+                shift_jis: ManuallyDrop::new(CxxNarrowString::new_in(alloc)),
+            },
+            DLStringTag::EUC_JP => DLStringUnion {
+                euc_jp: ManuallyDrop::new(CxxNarrowString::new_in(alloc)),
+            },
+            DLStringTag::UTF32 => DLStringUnion {
+                utf32: ManuallyDrop::new(CxxUtf32String::new_in(alloc)),
+            },
+        };
+
+        Self { union, tag }
+    }
+
+    /// Creates a new string with the encoding `tag` in the allocator `alloc`,
+    /// encoding `s` with it.
+    ///
+    /// Returns [`None`] if `s` could not be encoded.
+    pub fn from_str_in(s: &str, tag: DLStringEncoding, alloc: DLStdAllocator) -> Option<Self> {
+        let mut string = Self::new_in(tag, alloc);
+
+        string.write(s).then_some(string)
+    }
+
+    /// Same as [`DLString::from_str_in`].
+    pub fn try_from_utf8(s: &str, tag: DLStringEncoding, alloc: DLStdAllocator) -> Option<Self> {
+        Self::from_str_in(s, tag, alloc)
+    }
+
+    /// Returns the encoding of the string.
+    pub fn encoding(&self) -> DLStringEncoding {
+        self.tag
+    }
+
+    /// Converts the string to the encoding `encoding`, reallocating it
+    /// with the same allocator.
+    ///
+    /// Returns `true` if the string could be decoded and encoded with `encoding`,
+    /// otherwise it returns `false` and has no effect.
+    pub fn convert_to(&mut self, encoding: DLStringEncoding) -> bool {
+        if self.tag == encoding {
+            return self.read().is_some();
+        }
+
+        let Some(s) = self.read().map(Cow::into_owned) else {
+            return false;
+        };
+
+        match Self::from_str_in(&s, encoding, self.allocator().clone()) {
+            Some(converted) => {
+                *self = converted;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the length of the string in bytes, excluding the null terminator.
+    pub fn len_bytes(&self) -> usize {
+        unsafe {
+            match self.tag {
+                DLStringTag::UTF8 => self.union.utf8.len(),
+                DLStringTag::UTF16 => self.union.utf16.len() * 2,
+                DLStringTag::ISO_8859 => self.union.iso_8859.len(),
+                DLStringTag::SJIS => self.union.shift_jis.len(),
+                DLStringTag::EUC_JP => self.union.euc_jp.len(),
+                DLStringTag::UTF32 => self.union.utf32.len() * 4,
+            }
+        }
+    }
+
+    /// Returns the number of characters in the string by decoding it.
+    ///
+    /// Returns [`None`] if the string does not contain data valid for its encoding.
+    pub fn len_chars(&self) -> Option<usize> {
+        Some(self.read()?.chars().count())
+    }
+
+    /// Returns whether the string is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len_bytes() == 0
+    }
+
+    /// Returns the capacity of the string in bytes, excluding the null terminator.
+    pub fn capacity_bytes(&self) -> usize {
+        unsafe {
+            match self.tag {
+                DLStringTag::UTF8 => self.union.utf8.capacity(),
+                DLStringTag::UTF16 => self.union.utf16.capacity() * 2,
+                DLStringTag::ISO_8859 => self.union.iso_8859.capacity(),
+                DLStringTag::SJIS => self.union.shift_jis.capacity(),
+                DLStringTag::EUC_JP => self.union.euc_jp.capacity(),
+                DLStringTag::UTF32 => self.union.utf32.capacity() * 4,
+            }
+        }
+    }
+
+    /// Returns the stored code units of the string without decoding them,
+    /// excluding the null terminator.
+    pub fn as_raw(&self) -> RawStringView<'_> {
+        unsafe {
+            match self.tag {
+                DLStringTag::UTF8 => RawStringView::UTF8(self.union.utf8.as_bytes()),
+                DLStringTag::UTF16 => RawStringView::UTF16(self.union.utf16.as_bytes()),
+                DLStringTag::ISO_8859 => RawStringView::ISO_8859(self.union.iso_8859.as_bytes()),
+                DLStringTag::SJIS => RawStringView::SJIS(self.union.shift_jis.as_bytes()),
+                DLStringTag::EUC_JP => RawStringView::EUC_JP(self.union.euc_jp.as_bytes()),
+                DLStringTag::UTF32 => RawStringView::UTF32(self.union.utf32.as_bytes()),
+            }
+        }
+    }
+
+    /// Returns the stored bytes of the string without decoding them,
+    /// excluding the null terminator.
+    ///
+    /// UTF-16 and UTF-32 code units are viewed as native endian bytes.
+    pub fn as_raw_bytes(&self) -> &[u8] {
+        match self.as_raw() {
+            RawStringView::UTF8(bytes)
+            | RawStringView::ISO_8859(bytes)
+            | RawStringView::SJIS(bytes)
+            | RawStringView::EUC_JP(bytes) => bytes,
+            RawStringView::UTF16(units) => unsafe {
+                slice::from_raw_parts(units.as_ptr() as *const u8, mem::size_of_val(units))
+            },
+            RawStringView::UTF32(units) => unsafe {
+                slice::from_raw_parts(units.as_ptr() as *const u8, mem::size_of_val(units))
+            },
+        }
+    }
+
+    /// Returns the stored UTF-16 code units of the string without transcoding them,
+    /// excluding the null terminator.
+    ///
+    /// Returns [`None`] if the string is not UTF-16 encoded.
+    pub fn as_utf16(&self) -> Option<&[u16]> {
+        match self.as_raw() {
+            RawStringView::UTF16(units) => Some(units),
+            _ => None,
+        }
+    }
+
+    /// Reads the string as UTF-16, replacing data invalid for its encoding
+    /// with [`char::REPLACEMENT_CHARACTER`].
+    ///
+    /// UTF-16 strings are copied as is, preserving unpaired surrogates.
+    pub fn to_utf16_lossy(&self) -> Vec<u16> {
+        let mut buf = Vec::new();
+
+        if self.read_utf16_into(&mut buf).is_none() {
+            buf.extend(self.read_lossy().encode_utf16());
+        }
+
+        buf
+    }
+
     /// Reads the string and returns a UTF-8 encoded copy or a reference
     /// to the original string containing valid UTF-8 data.
     /// 
@@ -77,37 +306,235 @@ impl DLString {
     /// 
     /// Uses [encoding-rs](https://docs.rs/encoding_rs/0.8.35/encoding_rs/index.html) for decoding.
     pub fn read<'a>(&'a self) -> Option<Cow<'a, str>> {
+        self.try_read().ok()
+    }
+
+    /// Reads the string like [`DLString::read`].
+    ///
+    /// # Errors:
+    /// - [`ReadError`] with the index of the first code unit that is invalid for the encoding.
+    pub fn try_read(&self) -> Result<Cow<'_, str>, ReadError> {
         unsafe {
             match self.tag {
                 DLStringTag::UTF8 => {
-                    let (result, _, is_err) = encoding_rs::UTF_8.decode(self.union.utf8.as_bytes());
-                    (!is_err).then_some(result)
+                    let bytes = self.union.utf8.as_bytes();
+                    let (result, _, is_err) = encoding_rs::UTF_8.decode(bytes);
+                    if !is_err {
+                        return Ok(result);
+                    }
+                    let unit_index = str::from_utf8(bytes).err().map_or(0, |e| e.valid_up_to());
+                    Err(ReadError {
+                        unit_index,
+                        kind: ReadErrorKind::Malformed,
+                    })
                 }
                 DLStringTag::UTF16 => {
-                    let result = OsString::from_wide(self.union.utf16.as_bytes());
-                    Some(Cow::Owned(result.to_str()?.to_owned()))
+                    let units = self.union.utf16.as_bytes();
+                    let mut result = String::with_capacity(units.len());
+                    let mut unit_index = 0;
+                    for ch in char::decode_utf16(units.iter().copied()) {
+                        let ch = ch.map_err(|_| ReadError {
+                            unit_index,
+                            kind: ReadErrorKind::Surrogate,
+                        })?;
+                        result.push(ch);
+                        unit_index += ch.len_utf16();
+                    }
+                    Ok(Cow::Owned(result))
                 }
                 DLStringTag::ISO_8859 => {
-                    let (result, _, is_err) =
-                        encoding_rs::ISO_8859_15.decode(self.union.iso_8859.as_bytes());
-                    (!is_err).then_some(result)
+                    decode_narrow(encoding_rs::ISO_8859_15, self.union.iso_8859.as_bytes())
                 }
                 DLStringTag::SJIS => {
-                    let (result, _, is_err) =
-                        encoding_rs::SHIFT_JIS.decode(self.union.shift_jis.as_bytes());
-                    (!is_err).then_some(result)
+                    decode_narrow(encoding_rs::SHIFT_JIS, self.union.shift_jis.as_bytes())
                 }
                 DLStringTag::EUC_JP => {
-                    let (result, _, is_err) =
-                        encoding_rs::EUC_JP.decode(self.union.euc_jp.as_bytes());
-                    (!is_err).then_some(result)
+                    decode_narrow(encoding_rs::EUC_JP, self.union.euc_jp.as_bytes())
+                }
+                DLStringTag::UTF32 => decode_utf32(self.union.utf32.as_bytes()).map(Cow::Owned),
+            }
+        }
+    }
+
+    /// Reads the string like [`DLString::read`] into `buf`, replacing its contents
+    /// while reusing its capacity.
+    ///
+    /// Returns [`None`] if the string contained data invalid for its encoding,
+    /// in which case `buf` is left empty.
+    pub fn read_into(&self, buf: &mut String) -> Option<()> {
+        buf.clear();
+
+        let is_ok = unsafe {
+            match self.tag {
+                DLStringTag::UTF8 => {
+                    decode_into(encoding_rs::UTF_8, self.union.utf8.as_bytes(), buf).is_ok()
+                }
+                DLStringTag::UTF16 => {
+                    let units = self.union.utf16.as_bytes();
+                    buf.reserve(units.len());
+                    char::decode_utf16(units.iter().copied())
+                        .try_for_each(|ch| ch.map(|ch| buf.push(ch)))
+                        .is_ok()
+                }
+                DLStringTag::ISO_8859 => {
+                    decode_into(encoding_rs::ISO_8859_15, self.union.iso_8859.as_bytes(), buf)
+                        .is_ok()
+                }
+                DLStringTag::SJIS => {
+                    decode_into(encoding_rs::SHIFT_JIS, self.union.shift_jis.as_bytes(), buf)
+                        .is_ok()
+                }
+                DLStringTag::EUC_JP => {
+                    decode_into(encoding_rs::EUC_JP, self.union.euc_jp.as_bytes(), buf).is_ok()
+                }
+                DLStringTag::UTF32 => decode_utf32_into(self.union.utf32.as_bytes(), buf).is_ok(),
+            }
+        };
+
+        if !is_ok {
+            buf.clear();
+        }
+
+        is_ok.then_some(())
+    }
+
+    /// Reads the string as UTF-16 into `buf`, replacing its contents
+    /// while reusing its capacity.
+    ///
+    /// UTF-16 strings are copied as is, preserving unpaired surrogates.
+    ///
+    /// Returns [`None`] if the string contained data invalid for its encoding,
+    /// in which case `buf` is left empty.
+    pub fn read_utf16_into(&self, buf: &mut Vec<u16>) -> Option<()> {
+        buf.clear();
+
+        let is_ok = unsafe {
+            match self.tag {
+                DLStringTag::UTF8 => {
+                    decode_utf16_into(encoding_rs::UTF_8, self.union.utf8.as_bytes(), buf)
+                }
+                DLStringTag::UTF16 => {
+                    buf.extend_from_slice(self.union.utf16.as_bytes());
+                    true
+                }
+                DLStringTag::ISO_8859 => decode_utf16_into(
+                    encoding_rs::ISO_8859_15,
+                    self.union.iso_8859.as_bytes(),
+                    buf,
+                ),
+                DLStringTag::SJIS => {
+                    decode_utf16_into(encoding_rs::SHIFT_JIS, self.union.shift_jis.as_bytes(), buf)
+                }
+                DLStringTag::EUC_JP => {
+                    decode_utf16_into(encoding_rs::EUC_JP, self.union.euc_jp.as_bytes(), buf)
                 }
                 DLStringTag::UTF32 => {
-                    let mut result = String::new();
-                    for ch in self.union.utf32.as_bytes().iter() {
-                        result.push(char::from_u32(*ch)?);
-                    }
-                    Some(Cow::Owned(result))
+                    let units = self.union.utf32.as_bytes();
+                    buf.reserve(units.len());
+                    units
+                        .iter()
+                        .try_for_each(|&ch| {
+                            let ch = char::from_u32(ch)?;
+                            buf.extend_from_slice(ch.encode_utf16(&mut [0; 2]));
+                            Some(())
+                        })
+                        .is_some()
+                }
+            }
+        };
+
+        if !is_ok {
+            buf.clear();
+        }
+
+        is_ok.then_some(())
+    }
+
+    /// Reads the string into an [`OsString`] for use with Windows APIs.
+    ///
+    /// UTF-16 strings are converted as is, preserving unpaired surrogates.
+    ///
+    /// Returns [`None`] if the string contained data invalid for its encoding.
+    pub fn to_os_string(&self) -> Option<OsString> {
+        if let Some(units) = self.as_utf16() {
+            return Some(OsString::from_wide(units));
+        }
+
+        let mut buf = Vec::new();
+        self.read_utf16_into(&mut buf)?;
+
+        Some(OsString::from_wide(&buf))
+    }
+
+    /// Reads the string as null terminated UTF-16 for use as a `PCWSTR`.
+    ///
+    /// Data invalid for the encoding of the string is replaced with
+    /// [`char::REPLACEMENT_CHARACTER`], and UTF-16 strings are copied as is.
+    pub fn to_pcwstr_buf(&self) -> Vec<u16> {
+        let mut buf = self.to_utf16_lossy();
+        buf.push(0);
+        buf
+    }
+
+    /// Reads the string like [`DLString::read`], but replaces data invalid
+    /// for its encoding with [`char::REPLACEMENT_CHARACTER`].
+    pub fn read_lossy(&self) -> Cow<'_, str> {
+        unsafe {
+            match self.tag {
+                DLStringTag::UTF8 => String::from_utf8_lossy(self.union.utf8.as_bytes()),
+                DLStringTag::UTF16 => {
+                    Cow::Owned(String::from_utf16_lossy(self.union.utf16.as_bytes()))
+                }
+                DLStringTag::ISO_8859 => {
+                    encoding_rs::ISO_8859_15
+                        .decode(self.union.iso_8859.as_bytes())
+                        .0
+                }
+                DLStringTag::SJIS => {
+                    encoding_rs::SHIFT_JIS
+                        .decode(self.union.shift_jis.as_bytes())
+                        .0
+                }
+                DLStringTag::EUC_JP => encoding_rs::EUC_JP.decode(self.union.euc_jp.as_bytes()).0,
+                DLStringTag::UTF32 => Cow::Owned(
+                    self.union
+                        .utf32
+                        .as_bytes()
+                        .iter()
+                        .map(|&ch| char::from_u32(ch).unwrap_or(char::REPLACEMENT_CHARACTER))
+                        .collect(),
+                ),
+            }
+        }
+    }
+
+    /// Compares the string with `other` with the same case and separator folding
+    /// as its [`DLHash`], see [`crate::hash::eq_dlcase`].
+    ///
+    /// Only allocates if the encoding is narrow and `other` is not ASCII.
+    pub fn eq_dlcase(&self, other: &str) -> bool {
+        unsafe {
+            match self.tag {
+                DLStringTag::UTF8 => {
+                    eq_dlcase_units(self.union.utf8.as_bytes().iter().copied(), other.bytes())
+                }
+                DLStringTag::UTF16 => eq_dlcase_units(
+                    self.union.utf16.as_bytes().iter().copied(),
+                    other.encode_utf16(),
+                ),
+                DLStringTag::ISO_8859 => eq_dlcase_narrow(
+                    encoding_rs::ISO_8859_15,
+                    self.union.iso_8859.as_bytes(),
+                    other,
+                ),
+                DLStringTag::SJIS => {
+                    eq_dlcase_narrow(encoding_rs::SHIFT_JIS, self.union.shift_jis.as_bytes(), other)
+                }
+                DLStringTag::EUC_JP => {
+                    eq_dlcase_narrow(encoding_rs::EUC_JP, self.union.euc_jp.as_bytes(), other)
+                }
+                DLStringTag::UTF32 => {
+                    eq_dlcase_units(self.union.utf32.as_bytes().iter().copied(), other.chars())
                 }
             }
         }
@@ -117,78 +544,548 @@ impl DLString {
     /// the contents of `self` with `s`.
     /// 
     /// Returns `true` if the string could be encoded, otherwise it returns `false`
-    /// and has no effect.
+    /// and has no effect. Use [`DLString::try_write`] to find the character that
+    /// could not be encoded.
     /// 
     /// Uses [encoding-rs](https://crates.io/crates/encoding_rs) for encoding.
     pub fn write<T: AsRef<str>>(&mut self, s: T) -> bool {
+        self.try_write(s).is_ok()
+    }
+
+    /// Encodes the provided UTF-8 string with the source encoding and replaces
+    /// the contents of `self` with `s`.
+    ///
+    /// # Errors:
+    /// - [`WriteError`] with the index of the first character that could not be encoded,
+    ///   in which case it has no effect.
+    pub fn try_write<T: AsRef<str>>(&mut self, s: T) -> Result<(), WriteError> {
+        self.write_with(s.as_ref(), false).map(|_| ())
+    }
+
+    /// Encodes the provided UTF-8 string with the source encoding and replaces
+    /// the contents of `self` with `s`, substituting `?` for characters that
+    /// can't be encoded.
+    ///
+    /// Returns the number of substituted characters.
+    pub fn write_lossy<T: AsRef<str>>(&mut self, s: T) -> usize {
+        // Never fails when substituting characters
+        self.write_with(s.as_ref(), true).unwrap_or_default()
+    }
+
+    /// Replaces the contents of `self` with UTF-16 `units`.
+    ///
+    /// UTF-16 strings are written as is, preserving unpaired surrogates. For other encodings,
+    /// `units` are decoded and encoded like [`DLString::write`].
+    ///
+    /// Returns `true` if the string could be written, otherwise it returns `false`
+    /// and has no effect. Unpaired surrogates can't be written in other encodings.
+    pub fn write_utf16(&mut self, units: &[u16]) -> bool {
+        if self.tag == DLStringTag::UTF16 {
+            unsafe {
+                let dst = &mut *self.union.utf16;
+                dst.replace(units);
+            }
+
+            return true;
+        }
+
+        String::from_utf16(units).is_ok_and(|s| self.write(s))
+    }
+
+    /// Replaces the contents of `self` with `s`, like [`DLString::write_utf16`].
+    ///
+    /// Returns `true` if the string could be written, otherwise it returns `false`
+    /// and has no effect.
+    pub fn write_os_str(&mut self, s: &OsStr) -> bool {
+        let units: Vec<u16> = s.encode_wide().collect();
+        self.write_utf16(&units)
+    }
+
+    /// Encodes the provided UTF-8 string with the source encoding and appends it to `self`.
+    ///
+    /// Returns `true` if the string could be encoded, otherwise it returns `false`
+    /// and has no effect.
+    pub fn push_str(&mut self, s: &str) -> bool {
         unsafe {
             match self.tag {
                 DLStringTag::UTF8 => {
                     let dst = &mut *self.union.utf8;
-                    let (result, _, is_err) = encoding_rs::UTF_8.encode(s.as_ref());
-                    if !is_err {
-                        *dst = CxxUtf8String::from_bytes_in(result, dst.allocator().clone());
-                    }
-                    !is_err
+                    dst.push(s);
+                    true
                 }
                 DLStringTag::UTF16 => {
                     let dst = &mut *self.union.utf16;
-                    *dst = CxxUtf16String::new_in(dst.allocator().clone());
-                    dst.extend(s.as_ref().encode_utf16());
+                    dst.push(s.encode_utf16().collect::<Vec<_>>());
                     true
                 }
                 DLStringTag::ISO_8859 => {
                     let dst = &mut *self.union.iso_8859;
-                    let (result, _, is_err) = encoding_rs::ISO_8859_15.encode(s.as_ref());
-                    if !is_err {
-                        *dst = CxxNarrowString::from_bytes_in(result, dst.allocator().clone());
-                    }
-                    !is_err
+                    encode_narrow(encoding_rs::ISO_8859_15, s, false)
+                        .map(|(result, _)| dst.push(result))
+                        .is_ok()
                 }
                 DLStringTag::SJIS => {
                     let dst = &mut *self.union.shift_jis;
-                    let (result, _, is_err) = encoding_rs::SHIFT_JIS.encode(s.as_ref());
-                    if !is_err {
-                        *dst = CxxNarrowString::from_bytes_in(result, dst.allocator().clone());
-                    }
-                    !is_err
+                    encode_narrow(encoding_rs::SHIFT_JIS, s, false)
+                        .map(|(result, _)| dst.push(result))
+                        .is_ok()
                 }
                 DLStringTag::EUC_JP => {
                     let dst = &mut *self.union.euc_jp;
-                    let (result, _, is_err) = encoding_rs::EUC_JP.encode(s.as_ref());
-                    if !is_err {
-                        *dst = CxxNarrowString::from_bytes_in(result, dst.allocator().clone());
-                    }
-                    !is_err
+                    encode_narrow(encoding_rs::EUC_JP, s, false)
+                        .map(|(result, _)| dst.push(result))
+                        .is_ok()
                 }
                 DLStringTag::UTF32 => {
                     let dst = &mut *self.union.utf32;
-                    *dst = CxxUtf32String::new_in(dst.allocator().clone());
-                    dst.extend(s.as_ref().chars().map(|c| c as u32));
+                    dst.push(s.chars().map(|c| c as u32).collect::<Vec<_>>());
                     true
                 }
             }
         }
     }
+
+    fn write_with(&mut self, s: &str, lossy: bool) -> Result<usize, WriteError> {
+        unsafe {
+            match self.tag {
+                DLStringTag::UTF8 => {
+                    let dst = &mut *self.union.utf8;
+                    dst.replace(s);
+                    Ok(0)
+                }
+                DLStringTag::UTF16 => {
+                    // `extend` reserves on top of the capacity, so it always reallocates
+                    let dst = &mut *self.union.utf16;
+                    dst.replace(s.encode_utf16().collect::<Vec<_>>());
+                    Ok(0)
+                }
+                DLStringTag::ISO_8859 => {
+                    let dst = &mut *self.union.iso_8859;
+                    let (result, replaced) = encode_narrow(encoding_rs::ISO_8859_15, s, lossy)?;
+                    dst.replace(result);
+                    Ok(replaced)
+                }
+                DLStringTag::SJIS => {
+                    let dst = &mut *self.union.shift_jis;
+                    let (result, replaced) = encode_narrow(encoding_rs::SHIFT_JIS, s, lossy)?;
+                    dst.replace(result);
+                    Ok(replaced)
+                }
+                DLStringTag::EUC_JP => {
+                    let dst = &mut *self.union.euc_jp;
+                    let (result, replaced) = encode_narrow(encoding_rs::EUC_JP, s, lossy)?;
+                    dst.replace(result);
+                    Ok(replaced)
+                }
+                DLStringTag::UTF32 => {
+                    let dst = &mut *self.union.utf32;
+                    dst.replace(s.chars().map(|c| c as u32).collect::<Vec<_>>());
+                    Ok(0)
+                }
+            }
+        }
+    }
+
+    fn allocator(&self) -> &DLStdAllocator {
+        unsafe {
+            match self.tag {
+                DLStringTag::UTF8 => self.union.utf8.allocator(),
+                DLStringTag::UTF16 => self.union.utf16.allocator(),
+                DLStringTag::ISO_8859 => self.union.iso_8859.allocator(),
+                DLStringTag::SJIS => self.union.shift_jis.allocator(),
+                DLStringTag::EUC_JP => self.union.euc_jp.allocator(),
+                DLStringTag::UTF32 => self.union.utf32.allocator(),
+            }
+        }
+    }
 }
 
 impl DLHashString {
+    /// Creates a new string with the encoding `tag` in the allocator `alloc`,
+    /// encoding `s` with it. Its hash is computed on first use.
+    ///
+    /// `vtable` must point to the `DLTX::DLBasicHashString` virtual function table
+    /// of the game, since game code may call its virtual functions.
+    ///
+    /// Returns [`None`] if `s` could not be encoded.
+    pub fn from_str_in(
+        s: &str,
+        tag: DLStringEncoding,
+        alloc: DLStdAllocator,
+        vtable: usize,
+    ) -> Option<Self> {
+        Some(Self {
+            _vtable: vtable,
+            string: DLString::from_str_in(s, tag, alloc)?,
+            hash: DLStringHash {
+                value: AtomicU32::new(0),
+                has_value: AtomicBool::new(false),
+            },
+        })
+    }
+
     pub fn read(&self) -> Option<Cow<'_, str>> {
         self.string.read()
     }
 
-    pub fn write<T: AsRef<str>>(&mut self, s: T) {
-        self.string.write(s);
+    /// See [`DLString::encoding`].
+    pub fn encoding(&self) -> DLStringEncoding {
+        self.string.encoding()
+    }
+
+    /// The `DLTX::DLBasicHashString` virtual function table of the string,
+    /// see [`DLHashString::from_str_in`].
+    pub fn vtable(&self) -> usize {
+        self._vtable
+    }
+
+    /// Returns the cached hash of the string, or [`None`] if it has not been computed yet.
+    ///
+    /// Unlike [`DLHash::strhash`], it never computes the hash.
+    pub fn strhash_if_cached(&self) -> Option<u32> {
+        self.hash.get()
+    }
+
+    /// See [`DLString::len_bytes`].
+    pub fn len_bytes(&self) -> usize {
+        self.string.len_bytes()
+    }
+
+    /// See [`DLString::len_chars`].
+    pub fn len_chars(&self) -> Option<usize> {
+        self.string.len_chars()
+    }
+
+    /// See [`DLString::is_empty`].
+    pub fn is_empty(&self) -> bool {
+        self.string.is_empty()
+    }
+
+    /// See [`DLString::capacity_bytes`].
+    pub fn capacity_bytes(&self) -> usize {
+        self.string.capacity_bytes()
+    }
+
+    /// See [`DLString::write`]. Invalidates the cached hash if `s` was written.
+    pub fn write<T: AsRef<str>>(&mut self, s: T) -> bool {
+        self.try_write(s).is_ok()
+    }
+
+    /// See [`DLString::try_write`]. Invalidates the cached hash if `s` was written.
+    pub fn try_write<T: AsRef<str>>(&mut self, s: T) -> Result<(), WriteError> {
+        self.string.try_write(s)?;
         self.hash.has_value.store(false, Ordering::Relaxed);
+
+        Ok(())
+    }
+
+    /// See [`DLString::eq_dlcase`].
+    pub fn eq_dlcase(&self, other: &str) -> bool {
+        self.string.eq_dlcase(other)
+    }
+
+    /// See [`DLString::write_utf16`]. Invalidates the cached hash if `units` were written.
+    pub fn write_utf16(&mut self, units: &[u16]) -> bool {
+        let written = self.string.write_utf16(units);
+
+        if written {
+            self.hash.has_value.store(false, Ordering::Relaxed);
+        }
+
+        written
+    }
+
+    /// See [`DLString::push_str`]. Invalidates the cached hash if `s` was appended.
+    pub fn push_str(&mut self, s: &str) -> bool {
+        let pushed = self.string.push_str(s);
+
+        if pushed {
+            self.hash.has_value.store(false, Ordering::Relaxed);
+        }
+
+        pushed
     }
 }
 
+/// Decodes `bytes` with a narrow `encoding` like [`encoding_rs::Encoding::decode`],
+/// finding the first malformed byte if they are invalid.
+fn decode_narrow<'a>(
+    encoding: &'static encoding_rs::Encoding,
+    bytes: &'a [u8],
+) -> Result<Cow<'a, str>, ReadError> {
+    let (result, _, is_err) = encoding.decode(bytes);
+
+    if !is_err {
+        return Ok(result);
+    }
+
+    // Decode again with the same BOM handling to find the malformed byte
+    let error = decode_into(encoding, bytes, &mut String::new()).err();
+
+    Err(error.unwrap_or(ReadError {
+        unit_index: bytes.len(),
+        kind: ReadErrorKind::Malformed,
+    }))
+}
+
+/// Decodes `bytes` with `encoding` like [`encoding_rs::Encoding::decode`],
+/// appending the result to `dst`.
+fn decode_into(
+    encoding: &'static encoding_rs::Encoding,
+    bytes: &[u8],
+    dst: &mut String,
+) -> Result<(), ReadError> {
+    let mut decoder = encoding.new_decoder();
+
+    let mut read = 0;
+
+    loop {
+        let additional = decoder
+            .max_utf8_buffer_length_without_replacement(bytes.len() - read)
+            .unwrap_or(bytes.len() - read);
+
+        dst.reserve(additional.max(4));
+
+        let (result, len) =
+            decoder.decode_to_string_without_replacement(&bytes[read..], dst, true);
+
+        read += len;
+
+        match result {
+            DecoderResult::InputEmpty => return Ok(()),
+            DecoderResult::OutputFull => {}
+            DecoderResult::Malformed(bad_len, extra_len) => {
+                return Err(ReadError {
+                    unit_index: read.saturating_sub(bad_len as usize + extra_len as usize),
+                    kind: ReadErrorKind::Malformed,
+                });
+            }
+        }
+    }
+}
+
+/// Decodes `bytes` with `encoding` like [`encoding_rs::Encoding::decode`],
+/// appending the UTF-16 result to `dst`.
+fn decode_utf16_into(
+    encoding: &'static encoding_rs::Encoding,
+    bytes: &[u8],
+    dst: &mut Vec<u16>,
+) -> bool {
+    let mut decoder = encoding.new_decoder();
+
+    let Some(max_len) = decoder.max_utf16_buffer_length(bytes.len()) else {
+        return false;
+    };
+
+    let start = dst.len();
+    dst.resize(start + max_len, 0);
+
+    let (result, _, written) =
+        decoder.decode_to_utf16_without_replacement(bytes, &mut dst[start..], true);
+
+    dst.truncate(start + written);
+
+    matches!(result, DecoderResult::InputEmpty)
+}
+
+/// Validates `units` in a first pass, then decodes them into a [`String`]
+/// with the exact capacity.
+fn decode_utf32(units: &[u32]) -> Result<String, ReadError> {
+    let mut result = String::new();
+    decode_utf32_into(units, &mut result)?;
+
+    Ok(result)
+}
+
+/// Validates `units` in a first pass, then decodes them, appending the result to `dst`
+/// after reserving the exact capacity.
+fn decode_utf32_into(units: &[u32], dst: &mut String) -> Result<(), ReadError> {
+    let mut len = 0;
+
+    for (unit_index, &ch) in units.iter().enumerate() {
+        let kind = match ch {
+            0..0x80 => {
+                len += 1;
+                continue;
+            }
+            0x80..0x800 => {
+                len += 2;
+                continue;
+            }
+            0x800..0xD800 | 0xE000..0x10000 => {
+                len += 3;
+                continue;
+            }
+            0xD800..=0xDFFF => ReadErrorKind::Surrogate,
+            0x10000..=0x10FFFF => {
+                len += 4;
+                continue;
+            }
+            _ => ReadErrorKind::OutOfRange,
+        };
+
+        return Err(ReadError { unit_index, kind });
+    }
+
+    dst.reserve(len);
+
+    // SAFETY: every unit is a valid scalar value
+    dst.extend(units.iter().map(|&ch| unsafe { char::from_u32_unchecked(ch) }));
+
+    Ok(())
+}
+
+/// Compares `bytes` encoded with a narrow `encoding` with `other` like [`DLString::eq_dlcase`].
+fn eq_dlcase_narrow(encoding: &'static encoding_rs::Encoding, bytes: &[u8], other: &str) -> bool {
+    // ASCII is encoded as is by the narrow encodings
+    if other.is_ascii() {
+        return eq_dlcase_units(bytes.iter().copied(), other.bytes());
+    }
+
+    encode_narrow(encoding, other, false)
+        .is_ok_and(|(other, _)| eq_dlcase_units(bytes.iter().copied(), other))
+}
+
+/// Encodes `s` with a narrow `encoding`, returning the encoded bytes and the number of
+/// characters substituted with `?` if `lossy` is `true`.
+///
+/// Unlike [`encoding_rs::Encoding::encode`], it does not substitute HTML character references.
+fn encode_narrow(
+    encoding: &'static encoding_rs::Encoding,
+    s: &str,
+    lossy: bool,
+) -> Result<(Vec<u8>, usize), WriteError> {
+    let mut encoder = encoding.new_encoder();
+    let mut dst = Vec::with_capacity(s.len());
+
+    let mut read = 0;
+    let mut replaced = 0;
+
+    loop {
+        let (result, len) =
+            encoder.encode_from_utf8_to_vec_without_replacement(&s[read..], &mut dst, true);
+
+        read += len;
+
+        match result {
+            EncoderResult::InputEmpty => return Ok((dst, replaced)),
+            EncoderResult::OutputFull => {
+                let additional = encoder
+                    .max_buffer_length_from_utf8_without_replacement(s.len() - read)
+                    .unwrap_or(s.len() - read);
+
+                dst.reserve(additional.max(1));
+            }
+            EncoderResult::Unmappable(_) if lossy => {
+                dst.push(b'?');
+                replaced += 1;
+            }
+            EncoderResult::Unmappable(_) => {
+                // The unmappable character has been read
+                let char_index = s[..read].chars().count() - 1;
+                return Err(WriteError { char_index });
+            }
+        }
+    }
+}
+
+impl fmt::Display for WriteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "character {} can't be encoded", self.char_index)
+    }
+}
+
+impl error::Error for WriteError {}
+
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} code unit at index {}", self.kind, self.unit_index)
+    }
+}
+
+impl error::Error for ReadError {}
+
 impl fmt::Debug for DLString {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         fmt::Debug::fmt(&self.read(), f)
     }
 }
 
+impl fmt::Display for DLString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.read_lossy())
+    }
+}
+
+/// Strings are equal if they have the same encoding and the same code units.
+impl PartialEq for DLString {
+    fn eq(&self, other: &Self) -> bool {
+        if self.tag != other.tag {
+            return false;
+        }
+
+        unsafe {
+            match self.tag {
+                DLStringTag::UTF8 => self.union.utf8.as_bytes() == other.union.utf8.as_bytes(),
+                DLStringTag::UTF16 => self.union.utf16.as_bytes() == other.union.utf16.as_bytes(),
+                DLStringTag::ISO_8859 => {
+                    self.union.iso_8859.as_bytes() == other.union.iso_8859.as_bytes()
+                }
+                DLStringTag::SJIS => {
+                    self.union.shift_jis.as_bytes() == other.union.shift_jis.as_bytes()
+                }
+                DLStringTag::EUC_JP => {
+                    self.union.euc_jp.as_bytes() == other.union.euc_jp.as_bytes()
+                }
+                DLStringTag::UTF32 => self.union.utf32.as_bytes() == other.union.utf32.as_bytes(),
+            }
+        }
+    }
+}
+
+impl Eq for DLString {}
+
+/// Compares the decoded string with `other`.
+impl PartialEq<str> for DLString {
+    fn eq(&self, other: &str) -> bool {
+        unsafe {
+            match self.tag {
+                DLStringTag::UTF8 => self.union.utf8.as_bytes() == other.as_bytes(),
+                DLStringTag::UTF16 => self
+                    .union
+                    .utf16
+                    .as_bytes()
+                    .iter()
+                    .copied()
+                    .eq(other.encode_utf16()),
+                DLStringTag::UTF32 => self
+                    .union
+                    .utf32
+                    .as_bytes()
+                    .iter()
+                    .copied()
+                    .eq(other.chars().map(|c| c as u32)),
+                DLStringTag::ISO_8859 | DLStringTag::SJIS | DLStringTag::EUC_JP => {
+                    self.read().is_some_and(|s| s == other)
+                }
+            }
+        }
+    }
+}
+
+impl PartialEq<&str> for DLString {
+    fn eq(&self, other: &&str) -> bool {
+        *self == **other
+    }
+}
+
+/// Hashes the [`DLHash`] of the string, so equal strings hash equally.
+impl Hash for DLString {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u32(self.strhash());
+    }
+}
+
 impl Drop for DLString {
     fn drop(&mut self) {
         unsafe {
@@ -249,27 +1146,244 @@ impl DLHash for DLString {
     }
 }
 
+impl DLHash64 for DLString {
+    fn strhash64(&self) -> u64 {
+        unsafe {
+            match self.tag {
+                DLStringTag::UTF8 => self.union.utf8.as_bytes().strhash64(),
+                DLStringTag::UTF16 => self.union.utf16.as_bytes().strhash64(),
+                DLStringTag::ISO_8859 => self.union.iso_8859.as_bytes().strhash64(),
+                DLStringTag::SJIS => self.union.shift_jis.as_bytes().strhash64(),
+                DLStringTag::EUC_JP => self.union.euc_jp.as_bytes().strhash64(),
+                DLStringTag::UTF32 => self.union.utf32.as_bytes().strhash64(),
+            }
+        }
+    }
+}
+
 impl Clone for DLHashString {
     fn clone(&self) -> Self {
         Self {
             _vtable: self._vtable,
             string: self.string.clone(),
-            hash: unsafe { std::ptr::read(&self.hash) },
+            hash: self.hash.clone(),
+        }
+    }
+}
+
+impl DLStringHash {
+    fn get(&self) -> Option<u32> {
+        // Pairs with the release store in `strhash`, so the cached value is visible
+        self.has_value
+            .load(Ordering::Acquire)
+            .then(|| self.value.load(Ordering::Relaxed))
+    }
+}
+
+impl Clone for DLStringHash {
+    fn clone(&self) -> Self {
+        let value = self.get();
+
+        Self {
+            value: AtomicU32::new(value.unwrap_or_default()),
+            has_value: AtomicBool::new(value.is_some()),
         }
     }
 }
 
 impl DLHash for DLHashString {
     fn strhash(&self) -> u32 {
-        if self.hash.has_value.fetch_or(true, Ordering::AcqRel) {
-            let hash = self.string.strhash();
-            self.hash.value.store(hash, Ordering::Relaxed);
+        if let Some(hash) = self.hash.get() {
+            return hash;
         }
 
-        self.hash.value.load(Ordering::Relaxed)
+        // Concurrent callers may all compute the hash, but they store the same value
+        let hash = self.string.strhash();
+
+        self.hash.value.store(hash, Ordering::Relaxed);
+        self.hash.has_value.store(true, Ordering::Release);
+
+        hash
+    }
+}
+
+impl fmt::Display for DLHashString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.string, f)
+    }
+}
+
+impl PartialEq for DLHashString {
+    fn eq(&self, other: &Self) -> bool {
+        self.string == other.string
+    }
+}
+
+impl Eq for DLHashString {}
+
+impl PartialEq<str> for DLHashString {
+    fn eq(&self, other: &str) -> bool {
+        self.string == *other
+    }
+}
+
+impl PartialEq<&str> for DLHashString {
+    fn eq(&self, other: &&str) -> bool {
+        self.string == **other
+    }
+}
+
+/// Hashes the cached [`DLHash`] of the string.
+impl Hash for DLHashString {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u32(self.strhash());
     }
 }
 
 unsafe impl Send for DLHashString {}
 
 unsafe impl Sync for DLHashString {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash_string(s: &str, tag: DLStringEncoding) -> DLHashString {
+        DLHashString::from_str_in(s, tag, DLStdAllocator::default(), 0).unwrap()
+    }
+
+    #[test]
+    fn hash_string_caches_first_hash() {
+        for tag in [
+            DLStringEncoding::UTF8,
+            DLStringEncoding::UTF16,
+            DLStringEncoding::SJIS,
+            DLStringEncoding::UTF32,
+        ] {
+            let mut s = hash_string("EquipParamWeapon", tag);
+
+            assert_eq!(s.strhash_if_cached(), None);
+            assert_eq!(s.strhash(), "EquipParamWeapon".strhash());
+            assert_eq!(s.strhash_if_cached(), Some("EquipParamWeapon".strhash()));
+            assert_eq!(s.strhash(), "EquipParamWeapon".strhash());
+
+            assert!(s.write("NpcParam"));
+
+            assert_eq!(s.strhash_if_cached(), None);
+            assert_eq!(s.strhash(), "NpcParam".strhash());
+        }
+    }
+
+    const ENCODINGS: [DLStringEncoding; 6] = [
+        DLStringEncoding::UTF8,
+        DLStringEncoding::UTF16,
+        DLStringEncoding::ISO_8859,
+        DLStringEncoding::SJIS,
+        DLStringEncoding::EUC_JP,
+        DLStringEncoding::UTF32,
+    ];
+
+    fn string(s: &str, tag: DLStringEncoding) -> DLString {
+        DLString::from_str_in(s, tag, DLStdAllocator::default()).unwrap()
+    }
+
+    #[test]
+    fn os_str_round_trip() {
+        for tag in ENCODINGS {
+            let mut s = string("Roundtable Hold", tag);
+
+            assert_eq!(s.to_os_string(), Some(OsString::from("Roundtable Hold")));
+
+            let wide: Vec<u16> = "Roundtable Hold".encode_utf16().chain([0]).collect();
+            assert_eq!(s.to_pcwstr_buf(), wide);
+
+            assert!(s.write_os_str(OsStr::new("Stormveil Castle")));
+            assert_eq!(s.read().as_deref(), Some("Stormveil Castle"));
+        }
+    }
+
+    #[test]
+    fn unpaired_surrogates_stay_in_utf16() {
+        let units = [0x41, 0xD800, 0x42];
+
+        let mut s = string("", DLStringEncoding::UTF16);
+
+        assert!(s.write_utf16(&units));
+        assert_eq!(s.as_utf16(), Some(&units[..]));
+        assert_eq!(s.to_pcwstr_buf(), [0x41, 0xD800, 0x42, 0]);
+
+        for tag in ENCODINGS.into_iter().filter(|&tag| tag != DLStringEncoding::UTF16) {
+            let mut s = string("unchanged", tag);
+
+            assert!(!s.write_utf16(&units));
+            assert_eq!(s.read().as_deref(), Some("unchanged"));
+        }
+    }
+
+    #[test]
+    fn hash_string_clone_copies_cache() {
+        let s = hash_string("SpEffectParam", DLStringEncoding::UTF16);
+        let hash = "SpEffectParam".strhash();
+
+        assert_eq!(s.clone().strhash_if_cached(), None);
+
+        s.strhash();
+
+        let clone = s.clone();
+
+        assert_eq!(clone.strhash_if_cached(), Some(hash));
+        assert_eq!(clone.read().as_deref(), Some("SpEffectParam"));
+        assert_eq!(clone, s);
+    }
+
+    #[test]
+    fn hash_string_clone_during_hashing() {
+        let hash = "ItemLotParam_map".strhash();
+
+        for _ in 0..100 {
+            let s = hash_string("ItemLotParam_map", DLStringEncoding::UTF8);
+
+            std::thread::scope(|scope| {
+                scope.spawn(|| s.strhash());
+
+                for _ in 0..100 {
+                    let cached = s.clone().strhash_if_cached();
+                    assert!(cached.is_none_or(|cached| cached == hash));
+                }
+            });
+        }
+    }
+
+    #[test]
+    fn write_reuses_buffer() {
+        const LONG: &str = "data0:/param/gameparam/gameparam.parambnd.dcx";
+
+        for tag in ENCODINGS {
+            let mut s = string(LONG, tag);
+
+            let ptr = s.as_raw_bytes().as_ptr();
+            let capacity = s.capacity_bytes();
+
+            assert!(s.write("regulation.bin"));
+            assert_eq!(s.read().as_deref(), Some("regulation.bin"));
+
+            assert!(s.write(&LONG[1..]));
+            assert_eq!(s.read().as_deref(), Some(&LONG[1..]));
+
+            assert!(s.write(&LONG[..6]));
+            assert!(s.push_str(&LONG[6..]));
+            assert_eq!(s.read().as_deref(), Some(LONG));
+
+            assert_eq!(s.as_raw_bytes().as_ptr(), ptr);
+            assert_eq!(s.capacity_bytes(), capacity);
+        }
+    }
+
+    #[test]
+    fn failed_write_has_no_effect() {
+        let mut s = string("Limgrave", DLStringEncoding::ISO_8859);
+
+        assert!(!s.write("リムグレイブ"));
+        assert_eq!(s.read().as_deref(), Some("Limgrave"));
+    }
+}
@@ -0,0 +1,76 @@
+//! [`serde`] support for [`DLString`] and [`DLHashString`].
+//!
+//! [`DLString`] serializes as its decoded text and [`DLHashString`] as `{ text, hash }`.
+//! Both deserialize into [`OwnedDLStringData`], which can be written into existing strings.
+
+use serde::{ser::SerializeStruct, Deserialize, Serialize};
+
+use super::{DLHashString, DLString, DLStringEncoding};
+use crate::{hash::DLHash, stdalloc::DLStdAllocator};
+
+/// Text deserialized from a serialized [`DLString`] or [`DLHashString`].
+///
+/// It is detached from the game and can be written into a string with
+/// [`OwnedDLStringData::write_into`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "OwnedDLStringRepr")]
+pub struct OwnedDLStringData {
+    /// The decoded text of the string.
+    pub text: String,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OwnedDLStringRepr {
+    Text(String),
+    HashString { text: String },
+}
+
+impl OwnedDLStringData {
+    /// Writes the text into `dst` with [`DLString::write`].
+    ///
+    /// Returns `true` if the text could be encoded, otherwise it returns `false`
+    /// and has no effect.
+    pub fn write_into(&self, dst: &mut DLString) -> bool {
+        dst.write(&self.text)
+    }
+}
+
+impl From<OwnedDLStringRepr> for OwnedDLStringData {
+    fn from(value: OwnedDLStringRepr) -> Self {
+        match value {
+            OwnedDLStringRepr::Text(text) | OwnedDLStringRepr::HashString { text } => Self { text },
+        }
+    }
+}
+
+/// Serializes the string decoded with [`DLString::read_lossy`].
+impl Serialize for DLString {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.read_lossy())
+    }
+}
+
+/// Serializes the string decoded with [`DLString::read_lossy`] and its [`DLHash`].
+impl Serialize for DLHashString {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("DLHashString", 2)?;
+        state.serialize_field("text", &self.string.read_lossy())?;
+        state.serialize_field("hash", &self.strhash())?;
+        state.end()
+    }
+}
+
+/// Deserializes a string into a UTF-8 [`DLHashString`] in the default allocator,
+/// suitable for offline use.
+///
+/// The virtual function table pointer is null, so the string must not be passed to the game.
+/// Prefer deserializing [`OwnedDLStringData`] and writing it into an existing string.
+impl<'de> Deserialize<'de> for DLHashString {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let OwnedDLStringData { text } = OwnedDLStringData::deserialize(deserializer)?;
+
+        DLHashString::from_str_in(&text, DLStringEncoding::UTF8, DLStdAllocator::default(), 0)
+            .ok_or_else(|| serde::de::Error::custom("string could not be encoded"))
+    }
+}